@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn abs_node() -> SoundNode {
     SoundNode {
         name: "Abs".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Applies absolute value to the waveform,
 bringing everything on the bottom of the waveform to the top."#
             .to_string(),
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn after_node() -> SoundNode {
     SoundNode {
         name: "After".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Plays audio 2 after audio 1 has finished playing."#.to_string(),
         inputs: BTreeMap::from([
             (
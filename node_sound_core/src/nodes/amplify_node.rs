@@ -1,5 +1,5 @@
 use crate::constants::MAX_FREQ;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -12,6 +12,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn amplify_node() -> SoundNode {
     SoundNode {
         name: "Amplify".to_string(),
+        category: NodeCategory::Dynamics,
         tooltip: r#"Amplifies the waveform making sounds louder."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -1,5 +1,5 @@
 use crate::constants::WAVE_TABLE_SIZE;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -14,6 +14,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn automated_bpm_sync_node() -> SoundNode {
     SoundNode {
         name: "Automated BPM Sync".to_string(),
+        category: NodeCategory::Sequencing,
         tooltip: r#"Syncs a wavetable to each note. 
 The automation values for Any is 0-21, 0-7 for the rest which corresponds to the automation input value."#
             .to_string(),
@@ -52,6 +53,15 @@ The automation values for Any is 0-21, 0-7 for the rest which corresponds to the
                     },
                 },
             ),
+            (
+                "lock to host".to_string(),
+                InputParameter {
+                    data_type: DataType::Float,
+                    kind: InputParamKind::ConnectionOrConstant,
+                    name: "lock to host".to_string(),
+                    value: InputValueConfig::Bool { value: false },
+                },
+            ),
         ]),
         outputs: BTreeMap::from([(
             "out".to_string(),
@@ -77,6 +87,7 @@ pub fn automated_bpm_sync_logic(mut props: SoundNodeProps) -> SoundNodeResult {
                     .get_graph("graph")?
                     .unwrap_or(vec![0.0; WAVE_TABLE_SIZE]),
                 props.note_speed(),
+                props.host_sync(),
             ))),
         },
     )]))
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn automated_clamp_node() -> SoundNode {
     SoundNode {
         name: "Automated Clamp Wave".to_string(),
+        category: NodeCategory::Dynamics,
         tooltip: r#"Automated version of the Clamp node.
 Clamp will make sure no values go above the maximum or below the minimum.
 min and max are waveforms going from -1.0 to 1.0."#
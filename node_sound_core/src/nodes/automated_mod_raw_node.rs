@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn automated_mod_raw_node() -> SoundNode {
     SoundNode {
         name: "Automated Mod Raw".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Automated version of the Mod Raw node.
 The mod amount is controlled by a waveform going from -1.0 to 1.0.
 Mod Raw uses the division remainder operator (mod) on a given wave."#
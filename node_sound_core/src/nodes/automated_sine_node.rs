@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn automated_sine_node() -> SoundNode {
     SoundNode {
         name: "Automated Sine Wave".to_string(),
+        category: NodeCategory::Oscillators,
         tooltip: r#"Automated version of the Sine node.
 Automates the frequency with a given waveform.
 Use TranslateWave to set the frequency values of the automation,
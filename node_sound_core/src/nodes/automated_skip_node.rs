@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn automated_skip_node() -> SoundNode {
     SoundNode {
         name: "Automated Skip".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Skips samples in the source for a given duration."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -1,5 +1,5 @@
 use crate::constants::MAX_FREQ;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -12,6 +12,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn automated_speed_node() -> SoundNode {
     SoundNode {
         name: "Automated Speed".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Changes the speed of the input waveform based off of the base frequency to the automation value."#
             .to_string(),
         inputs: BTreeMap::from([
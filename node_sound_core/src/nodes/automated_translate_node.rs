@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn automated_translate_node() -> SoundNode {
     SoundNode {
         name: "Automated Translate Wave".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Automated version of the Translate node.
 All parameters from the previous node are automated."#
             .to_string(),
@@ -1,5 +1,5 @@
 use crate::constants::{MIDDLE_C_FREQ, WAVE_TABLE_SIZE};
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn automated_wave_shaper_node() -> SoundNode {
     SoundNode {
         name: "Automated Wave Shaper".to_string(),
+        category: NodeCategory::Distortion,
         tooltip: r#"Automated version of the Wave Shaper node.
 Automates the frequency with a given waveform.
 Use TranslateWave to set the frequency values of the automation,
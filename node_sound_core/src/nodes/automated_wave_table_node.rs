@@ -1,5 +1,5 @@
 use crate::constants::MIDDLE_C_FREQ;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn automated_wave_table_node() -> SoundNode {
     SoundNode {
         name: "Automated Wave Table".to_string(),
+        category: NodeCategory::Oscillators,
         tooltip: r#"Automated version of the Wave Table node.
 Automates the frequency with a given waveform.
 Use TranslateWave to set the frequency values of the automation,
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn avg_node() -> SoundNode {
     SoundNode {
         name: "Avg".to_string(),
+        category: NodeCategory::Filters,
         tooltip: r#"Averages all values in a list of values determined by the length parameter.
         the values are cycled each time the table produces a sample."#
             .to_string(),
@@ -1,5 +1,5 @@
 use super::{SoundNodeProps, SoundNodeResult};
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use std::collections::BTreeMap;
 pub fn bit_crush_node() -> SoundNode {
     SoundNode {
         name: "Bit Crusher".to_string(),
+        category: NodeCategory::Distortion,
         tooltip: r#"Bit chrushes the given waveform."#.to_string(),
         inputs: BTreeMap::from([
             (
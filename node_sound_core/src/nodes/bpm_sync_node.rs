@@ -1,5 +1,5 @@
 use crate::constants::WAVE_TABLE_SIZE;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -14,6 +14,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn bpm_sync_node() -> SoundNode {
     SoundNode {
         name: "BPM Sync".to_string(),
+        category: NodeCategory::Sequencing,
         tooltip:
             r#"Syncs with the BPM to modify a waveform's amplitude with a wavetable every note.
         Note speed can be controlled with a dropdown of options."#
@@ -53,6 +54,15 @@ pub fn bpm_sync_node() -> SoundNode {
                     },
                 },
             ),
+            (
+                "lock to host".to_string(),
+                InputParameter {
+                    data_type: DataType::Float,
+                    kind: InputParamKind::ConnectionOrConstant,
+                    name: "lock to host".to_string(),
+                    value: InputValueConfig::Bool { value: false },
+                },
+            ),
         ]),
         outputs: BTreeMap::from([(
             "out".to_string(),
@@ -78,6 +88,7 @@ pub fn bpm_sync_logic(mut props: SoundNodeProps) -> SoundNodeResult {
                     .get_graph("graph")?
                     .unwrap_or(vec![0.0; WAVE_TABLE_SIZE]),
                 props.note_speed(),
+                props.host_sync(),
             ))),
         },
     )]))
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn clamp_node() -> SoundNode {
     SoundNode {
         name: "Clamp".to_string(),
+        category: NodeCategory::Dynamics,
         tooltip: r#"Clamps the given waveform between a min and max value,
 making sure no values go above or below the given min or max."#
             .to_string(),
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,7 +11,8 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn clamp_to_note_node() -> SoundNode {
     SoundNode {
         name: "Clamp To Note".to_string(),
-        tooltip: r#"Clamps the incoming value to the nearest note value. Should only be used after translate wave."#
+        category: NodeCategory::Math,
+        tooltip: r#"Clamps the incoming value to the nearest note of the patch's tuning. Should only be used after translate wave."#
             .to_string(),
         inputs: BTreeMap::from([(
             "audio 1".to_string(),
@@ -36,7 +37,7 @@ pub fn clamp_to_note_logic(mut props: SoundNodeProps) -> SoundNodeResult {
     Ok(BTreeMap::from([(
         "out".to_string(),
         ValueType::AudioSource {
-            value: props.push_sound(Box::new(ClampToNote::new(cloned))),
+            value: props.push_sound(Box::new(ClampToNote::new(cloned, props.tuning()))),
         },
     )]))
 }
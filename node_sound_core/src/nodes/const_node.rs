@@ -1,6 +1,6 @@
 use super::{SoundNodeProps, SoundNodeResult};
 use crate::constants::MAX_FREQ;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use std::collections::BTreeMap;
 pub fn const_node() -> SoundNode {
     SoundNode {
         name: "Const".to_string(),
+        category: NodeCategory::Sources,
         tooltip: r#"A waveform that just sits at a given value forever."#.to_string(),
         inputs: BTreeMap::from([(
             "amplitude".to_string(),
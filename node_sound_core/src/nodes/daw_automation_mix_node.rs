@@ -1,4 +1,5 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
+use crate::sound_graph::automation::AUTOMATION_COUNT;
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn daw_automation_mix_node() -> SoundNode {
     SoundNode {
         name: "Daw Automations Mix".to_string(),
+        category: NodeCategory::Automation,
         tooltip: r#"Mixes between audio 1 and audio 2 based on the daw parameter."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -62,7 +64,7 @@ pub fn daw_automation_mix_logic(mut props: SoundNodeProps) -> SoundNodeResult {
         ValueType::AudioSource {
             value: props.push_sound(Box::new(DawAutomationMix::new(
                 props.state._unserializeable_state.automations.0
-                    [(props.get_float("channel")?.round() as usize).clamp(0, 17)]
+                    [(props.get_float("channel")?.round() as usize).clamp(0, AUTOMATION_COUNT - 1)]
                 .clone(),
                 cloned1,
                 cloned2,
@@ -1,4 +1,5 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
+use crate::sound_graph::automation::AUTOMATION_COUNT;
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,17 +11,16 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn daw_automation_source_node() -> SoundNode {
     SoundNode {
         name: "Daw Automations".to_string(),
-        tooltip: r#"Daw automation parameters 1-18 can be accessed through this node."#.to_string(),
+        category: NodeCategory::Automation,
+        tooltip: r#"Outputs the value of an automation macro, picked by name. Macros are named and given a range and unit in the automation panel."#.to_string(),
         inputs: BTreeMap::from([(
-            "channel".to_string(),
+            "macro".to_string(),
             InputParameter {
-                data_type: DataType::Float,
-                kind: InputParamKind::ConnectionOnly,
-                name: "channel".to_string(),
-                value: InputValueConfig::Float {
-                    value: 0.0,
-                    min: 0.0,
-                    max: 17.0,
+                data_type: DataType::Dropdown,
+                kind: InputParamKind::ConstantOnly,
+                name: "macro".to_string(),
+                value: InputValueConfig::Macro {
+                    value: "A1".to_string(),
                 },
             },
         )]),
@@ -35,13 +35,24 @@ pub fn daw_automation_source_node() -> SoundNode {
 }
 
 pub fn daw_automation_source_logic(mut props: SoundNodeProps) -> SoundNodeResult {
+    let idx = match props.get_macro("macro") {
+        Ok(name) => match props.state.user_state.automation_macros.find(&name) {
+            Some(x) => x,
+            None => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    format!("No automation macro named {}", name),
+                )));
+            }
+        },
+        // Nodes saved before macros had names pick the parameter by number.
+        Err(_x) => (props.get_float("channel")?.round() as usize).clamp(0, AUTOMATION_COUNT - 1),
+    };
     Ok(BTreeMap::from([(
         "out".to_string(),
         ValueType::AudioSource {
             value: props.push_sound(Box::new(DawAutomationChannel::new(
-                props.state._unserializeable_state.automations.0
-                    [(props.get_float("channel")?.round() as usize).clamp(0, 17)]
-                .clone(),
+                props.state._unserializeable_state.automations.0[idx].clone(),
             ))),
         },
     )]))
@@ -1,5 +1,5 @@
 use super::{SoundNodeProps, SoundNodeResult};
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use std::collections::BTreeMap;
 pub fn delay_node() -> SoundNode {
     SoundNode {
         name: "Delay".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Delays the given waveform by an amount of time."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn delay_repeat_node() -> SoundNode {
     SoundNode {
         name: "DelayRepeat".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Acts more like a classic delay plugin"#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -1,5 +1,5 @@
 use super::{SoundNodeProps, SoundNodeResult};
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use std::collections::BTreeMap;
 pub fn duration_node() -> SoundNode {
     SoundNode {
         name: "Take Duration".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Takes a snapshot of the waveform for the amount of time you input."#
             .to_string(),
         inputs: BTreeMap::from([
@@ -1,6 +1,6 @@
 use super::{SoundNodeProps, SoundNodeResult};
 use crate::constants::MAX_FREQ;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -12,6 +12,7 @@ use std::str::FromStr;
 pub fn eq_node() -> SoundNode {
     SoundNode {
         name: "Eq".to_string(),
+        category: NodeCategory::Filters,
         tooltip: r#"Basic runtime EQ.
         Q factor controls how "selective" or "narrow" the filter is around the cutoff frequency.
         Low / High shelf and peak are the only settings that use gain. gain is in DB.
@@ -1,5 +1,5 @@
 use super::{SoundNodeProps, SoundNodeResult};
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use std::collections::BTreeMap;
 pub fn file_node() -> SoundNode {
     SoundNode {
         name: "Audio File".to_string(),
+        category: NodeCategory::Sources,
         tooltip: r#"Imports a wav, flac, or mp3 file as a waveform. Mono audio preferred."#
             .to_string(),
         inputs: BTreeMap::from([
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn flip_node() -> SoundNode {
     SoundNode {
         name: "Flip".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Flips the waveform vertically."#.to_string(),
         inputs: BTreeMap::from([(
             "audio 1".to_string(),
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn glitch_node() -> SoundNode {
     SoundNode {
         name: "Glitch".to_string(),
+        category: NodeCategory::Distortion,
         tooltip: r#"Glitch sounds."#.to_string(),
         inputs: BTreeMap::from([(
             "audio 1".to_string(),
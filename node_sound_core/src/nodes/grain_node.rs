@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn grain_node() -> SoundNode {
     SoundNode {
         name: "Grain".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Repeating of a given grain where the length and start of the grain can be automated."#.to_string(),
         inputs: BTreeMap::from([
             (
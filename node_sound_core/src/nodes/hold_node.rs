@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn hold_node() -> SoundNode {
     SoundNode {
         name: "Hold".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Holds a sample and repeats it for n seconds"#.to_string(),
         inputs: BTreeMap::from([
             (
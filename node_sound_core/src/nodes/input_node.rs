@@ -1,31 +1,81 @@
-use crate::nodes::SoundNode;
-use crate::sound_graph::graph_types::{DataType, Output, ValueType};
-use crate::sounds::input::InputChannel;
+use crate::nodes::{NodeCategory, SoundNode};
+use crate::sound_graph::graph_types::{
+    DataType, InputParameter, InputValueConfig, Output, ValueType,
+};
+use crate::sounds::input::{INPUT_BUSES, INPUT_CHANNELS, InputChannel};
+use egui_node_graph_2::InputParamKind;
 use std::collections::BTreeMap;
 
 use super::{SoundNodeProps, SoundNodeResult};
+
+fn dropdown_input(name: &str, values: &[&str]) -> (String, InputParameter) {
+    (
+        name.to_string(),
+        InputParameter {
+            data_type: DataType::Dropdown,
+            kind: InputParamKind::ConstantOnly,
+            name: name.to_string(),
+            value: InputValueConfig::Dropdown {
+                value: values[0].to_string(),
+                values: values.iter().map(|x| x.to_string()).collect(),
+            },
+        },
+    )
+}
+
+pub fn channel_input() -> (String, InputParameter) {
+    dropdown_input("channel", &INPUT_CHANNELS)
+}
+
+pub fn input_output() -> BTreeMap<String, Output> {
+    BTreeMap::from([(
+        "out".to_string(),
+        Output {
+            data_type: DataType::AudioSource,
+            name: "out".to_string(),
+        },
+    )])
+}
+
 pub fn input_node() -> SoundNode {
     SoundNode {
         name: "Daw Input".to_string(),
-        tooltip: r#"Input sound from DAW."#.to_string(),
-        inputs: BTreeMap::from([]),
-        outputs: BTreeMap::from([(
-            "out".to_string(),
-            Output {
-                data_type: DataType::AudioSource,
-                name: "out".to_string(),
-            },
-        )]),
+        category: NodeCategory::Sources,
+        tooltip: r#"Input sound from DAW. bus picks the main input or the sidechain, channel plays
+        both input channels or one of them on both outputs. Delays and other nodes that shift time
+        read the input from the last few seconds. In the synth every voice hears the live input,
+        whatever note it plays."#
+            .to_string(),
+        inputs: BTreeMap::from([dropdown_input("bus", &INPUT_BUSES), channel_input()]),
+        outputs: input_output(),
     }
 }
 
-pub fn input_logic(mut props: SoundNodeProps) -> SoundNodeResult {
+/// The position of a dropdown's value in `values`. Nodes saved before the dropdown existed use
+/// the first value.
+fn dropdown_index(props: &SoundNodeProps, name: &str, values: &[&str]) -> usize {
+    props
+        .get_dropdown(name)
+        .ok()
+        .and_then(|x| values.iter().position(|value| *value == x))
+        .unwrap_or(0)
+}
+
+pub fn input_source(mut props: SoundNodeProps, bus: usize) -> SoundNodeResult {
+    let channel = dropdown_index(&props, "channel", &INPUT_CHANNELS);
     Ok(BTreeMap::from([(
         "out".to_string(),
         ValueType::AudioSource {
             value: props.push_sound(Box::new(InputChannel::new(
                 props.state._unserializeable_state.input.0.clone(),
+                bus,
+                channel,
             ))),
         },
     )]))
 }
+
+pub fn input_logic(props: SoundNodeProps) -> SoundNodeResult {
+    let bus = dropdown_index(&props, "bus", &INPUT_BUSES);
+    input_source(props, bus)
+}
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn lfo_node() -> SoundNode {
     SoundNode {
         name: "Lfo".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Multiplies two waveforms together, works as a low frequency oscillator (LFO)."#
             .to_string(),
         inputs: BTreeMap::from([
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn merge_channels_node() -> SoundNode {
     SoundNode {
         name: "Merge Channels".to_string(),
+        category: NodeCategory::Utility,
         tooltip:
             r#"Merges the left audio of the two sources into a left and right singular source."#
                 .to_string(),
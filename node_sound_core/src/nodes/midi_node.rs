@@ -1,8 +1,8 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
-use crate::sounds::midi::MidiRenderer;
+use crate::sounds::midi::{MidiPlayer, MidiTempoMap, midi_notes};
 use egui_node_graph_2::InputParamKind;
 use std::collections::BTreeMap;
 
@@ -11,7 +11,9 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn midi_node() -> SoundNode {
     SoundNode {
         name: "Midi File".to_string(),
-        tooltip: r#"Imports and plays a midi file with the given waveform."#.to_string(),
+        category: NodeCategory::Sources,
+        tooltip: r#"Imports and plays a midi file, each note getting its own pitched copy of the input
+with the note's velocity and length. Follows the file's tempo changes unless synced to the host bpm."#.to_string(),
         inputs: BTreeMap::from([
             (
                 "file".to_string(),
@@ -40,6 +42,15 @@ pub fn midi_node() -> SoundNode {
                     value: InputValueConfig::Bool { value: false },
                 },
             ),
+            (
+                "sync to host bpm".to_string(),
+                InputParameter {
+                    data_type: DataType::Float,
+                    kind: InputParamKind::ConnectionOrConstant,
+                    name: "sync to host bpm".to_string(),
+                    value: InputValueConfig::Bool { value: false },
+                },
+            ),
         ]),
         outputs: BTreeMap::from([(
             "out".to_string(),
@@ -52,23 +63,31 @@ pub fn midi_node() -> SoundNode {
 }
 
 pub fn midi_logic(mut props: SoundNodeProps) -> SoundNodeResult {
-    props.update_wavetables_node_idx();
-    let file = props.get_midi("file")?;
-    if file.is_none() {
-        return Ok(BTreeMap::from([(
-            "out".to_string(),
-            ValueType::AudioSource { value: 0 },
-        )]));
-    }
+    let (song, tempo_changes) = match props.get_midi("file")? {
+        Some(x) => x,
+        None => {
+            return Ok(BTreeMap::from([(
+                "out".to_string(),
+                ValueType::AudioSource { value: 0 },
+            )]));
+        }
+    };
     let cloned = props.clone_sound(props.get_source("audio 1")?)?;
-    let midi = MidiRenderer::new(
+    let tempo = MidiTempoMap::new(song.time_unit as f64, song.bpm, &tempo_changes);
+    let host_bpm = match props.get_bool("sync to host bpm")? {
+        true => Some(props.bpm()),
+        false => None,
+    };
+    let midi = MidiPlayer::new(
         cloned,
-        file.unwrap().1,
+        midi_notes(&song),
+        tempo,
         props.get_bool("note independant")?,
         props.note_speed(),
         props.sample_rate(),
-        &mut props.state.user_state.wavetables,
-    );
+    )
+    .host_bpm(host_bpm)
+    .tuning(props.tuning());
     Ok(BTreeMap::from([(
         "out".to_string(),
         ValueType::AudioSource {
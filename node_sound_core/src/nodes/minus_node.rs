@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn minus_node() -> SoundNode {
     SoundNode {
         name: "Minus".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Subtracts audio 1 from audio 2 (destructive interference)"#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn mix_node() -> SoundNode {
     SoundNode {
         name: "Mix".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Mixes two waveforms together."#.to_string(),
         inputs: BTreeMap::from([
             (
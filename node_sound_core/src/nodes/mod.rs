@@ -2,14 +2,16 @@ use crate::{
     sound_graph::{
         graph::SoundNodeGraphState,
         graph_types::{InputParameter, Output, ValueType},
+        tuning::Tuning,
     },
-    sound_map::{DawSource, GenericSource},
-    sounds::{tracker::TrackerNote, wave_table::WaveTableManager},
+    sound_map::{AtomicF32, DawSource, GenericSource},
+    sounds::{tracker::TrackerNote, transport::HostTransport, wave_table::WaveTableManager},
 };
+use egui_node_graph_2::CategoryTrait;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{BTreeMap, HashMap},
-    sync::{Arc, Mutex},
+    sync::Arc,
     time::Duration,
 };
 use synthrs::midi::MidiSong;
@@ -61,6 +63,7 @@ pub mod mod_raw_node;
 pub mod no_op_node;
 pub mod noise_node;
 pub mod output_node;
+pub mod quantize_to_scale_node;
 pub mod random_duration_node;
 pub mod ref_node;
 pub mod repeat_infinite;
@@ -68,15 +71,18 @@ pub mod repeat_n_node;
 pub mod reverb_node;
 pub mod reverse_node;
 pub mod sawtooth_node;
+pub mod sidechain_input_node;
 pub mod signum_node;
 pub mod sine_node;
 pub mod skip_node;
 pub mod speed_node;
 pub mod split_channels_node;
 pub mod square_node;
+pub mod step_sequencer_node;
 pub mod switch_node;
 pub mod tracker_node;
 pub mod translate_node;
+pub mod transport_node;
 pub mod triangle_node;
 pub mod unison_node;
 pub mod vertical_wave_shaper_node;
@@ -121,10 +127,26 @@ impl<'a> SoundNodeProps<'a> {
         self.state._unserializeable_state.queue.get_note_speed()
     }
 
-    fn bpm(&self) -> Arc<Mutex<f32>> {
+    fn bpm(&self) -> Arc<AtomicF32> {
         self.state._unserializeable_state.queue.get_bpm()
     }
 
+    fn transport(&self) -> Arc<HostTransport> {
+        self.state._unserializeable_state.transport.0.clone()
+    }
+
+    fn tuning(&self) -> &Tuning {
+        &self.state.user_state.tuning
+    }
+
+    /// The host transport if the node's "lock to host" input is on.
+    fn host_sync(&self) -> Option<Arc<HostTransport>> {
+        match self.get_bool("lock to host") {
+            Ok(true) => Some(self.transport()),
+            _ => None,
+        }
+    }
+
     fn get_float(&self, name: &str) -> Result<f32, Box<dyn std::error::Error>> {
         Ok(self
             .inputs
@@ -171,7 +193,7 @@ impl<'a> SoundNodeProps<'a> {
     fn get_midi(
         &self,
         name: &str,
-    ) -> Result<Option<(String, MidiSong)>, Box<dyn std::error::Error>> {
+    ) -> Result<Option<(MidiSong, Vec<(u64, f64)>)>, Box<dyn std::error::Error>> {
         Ok(self
             .inputs
             .get(name)
@@ -195,6 +217,14 @@ impl<'a> SoundNodeProps<'a> {
             .clone()
             .try_to_dropdown()?)
     }
+    fn get_macro(&self, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(self
+            .inputs
+            .get(name)
+            .unwrap_or_default()
+            .clone()
+            .try_to_macro()?)
+    }
     fn get_tracker(&self, name: &str) -> Result<Vec<TrackerNote>, Box<dyn std::error::Error>> {
         Ok(self
             .inputs
@@ -205,9 +235,47 @@ impl<'a> SoundNodeProps<'a> {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum NodeCategory {
+    Oscillators,
+    Filters,
+    Distortion,
+    Dynamics,
+    Time,
+    Math,
+    Sources,
+    Sequencing,
+    Automation,
+    Utility,
+    Groups,
+}
+
+impl NodeCategory {
+    pub const ALL: [NodeCategory; 11] = [
+        NodeCategory::Oscillators,
+        NodeCategory::Filters,
+        NodeCategory::Distortion,
+        NodeCategory::Dynamics,
+        NodeCategory::Time,
+        NodeCategory::Math,
+        NodeCategory::Sources,
+        NodeCategory::Sequencing,
+        NodeCategory::Automation,
+        NodeCategory::Utility,
+        NodeCategory::Groups,
+    ];
+}
+
+impl CategoryTrait for NodeCategory {
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SoundNode {
     pub name: String,
+    pub category: NodeCategory,
     pub tooltip: String,
     pub inputs: BTreeMap<String, InputParameter>,
     pub outputs: BTreeMap<String, Output>,
@@ -324,6 +392,14 @@ pub fn get_nodes() -> NodeDefinitions {
             daw_automation_source_node::daw_automation_source_node(),
             Box::new(daw_automation_source_node::daw_automation_source_logic),
         ),
+        (
+            transport_node::transport_node(),
+            Box::new(transport_node::transport_logic),
+        ),
+        (
+            step_sequencer_node::step_sequencer_node(),
+            Box::new(step_sequencer_node::step_sequencer_logic),
+        ),
         (clamp_node::clamp_node(), Box::new(clamp_node::clamp_logic)),
         (abs_node::abs_node(), Box::new(abs_node::abs_logic)),
         (
@@ -374,6 +450,10 @@ pub fn get_nodes() -> NodeDefinitions {
         ),
         (avg_node::avg_node(), Box::new(avg_node::avg_logic)),
         (input_node::input_node(), Box::new(input_node::input_logic)),
+        (
+            sidechain_input_node::sidechain_input_node(),
+            Box::new(sidechain_input_node::sidechain_input_logic),
+        ),
         (ref_node::ref_node(), Box::new(ref_node::ref_logic)),
         (
             bpm_sync_node::bpm_sync_node(),
@@ -439,6 +519,10 @@ pub fn get_nodes() -> NodeDefinitions {
             clamp_to_note_node::clamp_to_note_node(),
             Box::new(clamp_to_note_node::clamp_to_note_logic),
         ),
+        (
+            quantize_to_scale_node::quantize_to_scale_node(),
+            Box::new(quantize_to_scale_node::quantize_to_scale_logic),
+        ),
         (
             automated_bpm_sync_node::automated_bpm_sync_node(),
             Box::new(automated_bpm_sync_node::automated_bpm_sync_logic),
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn mod_node() -> SoundNode {
     SoundNode {
         name: "Mod".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Stairsteps a given wave by a given value."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn mod_raw_node() -> SoundNode {
     SoundNode {
         name: "Mod Raw".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Takes the remainder of the wave and this value."#.to_string(),
         inputs: BTreeMap::from([
             (
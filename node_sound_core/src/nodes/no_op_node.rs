@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn no_op_node() -> SoundNode {
     SoundNode {
         name: "No Op".to_string(),
+        category: NodeCategory::Utility,
         tooltip: r#"Does nothing. Connect multiple nodes
 to this node to avoid reconnecting a bunch of stuff when you change a node in your graph."#
             .to_string(),
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn noise_node() -> SoundNode {
     SoundNode {
         name: "Noise".to_string(),
+        category: NodeCategory::Oscillators,
         tooltip: r#"Random noise waveform."#.to_string(),
         inputs: BTreeMap::from([
             (
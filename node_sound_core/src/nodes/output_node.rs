@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn output_node() -> SoundNode {
     SoundNode {
         name: "Output".to_string(),
+        category: NodeCategory::Utility,
         tooltip: r#"Finalized output audio to the DAW."#.to_string(),
         inputs: BTreeMap::from([(
             "audio 1".to_string(),
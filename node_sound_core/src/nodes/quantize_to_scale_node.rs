@@ -0,0 +1,157 @@
+use crate::constants::WAVE_TABLE_SIZE;
+use crate::nodes::{NodeCategory, SoundNode};
+use crate::sound_graph::graph_types::{
+    DataType, InputParameter, InputValueConfig, Output, ValueType,
+};
+use crate::sound_graph::note::Note;
+use crate::sounds::quantize_to_scale::{
+    CUSTOM_SCALE, QUANTIZE_SIGNALS, QuantizeSignal, QuantizeToScale, SCALES, scale_mask,
+};
+use egui_node_graph_2::InputParamKind;
+use std::collections::BTreeMap;
+
+use super::{SoundNodeProps, SoundNodeResult};
+
+fn dropdown_input(name: &str, value: String, values: Vec<String>) -> (String, InputParameter) {
+    (
+        name.to_string(),
+        InputParameter {
+            data_type: DataType::Dropdown,
+            kind: InputParamKind::ConstantOnly,
+            name: name.to_string(),
+            value: InputValueConfig::Dropdown { value, values },
+        },
+    )
+}
+
+fn float_input(name: &str, value: f32, min: f32, max: f32) -> (String, InputParameter) {
+    (
+        name.to_string(),
+        InputParameter {
+            data_type: DataType::Float,
+            kind: InputParamKind::ConnectionOrConstant,
+            name: name.to_string(),
+            value: InputValueConfig::Float { value, min, max },
+        },
+    )
+}
+
+pub fn quantize_to_scale_node() -> SoundNode {
+    SoundNode {
+        name: "Quantize to Scale".to_string(),
+        category: NodeCategory::Math,
+        tooltip:
+            r#"Snaps the incoming value to the nearest note of a scale. signal picks whether the
+        value is a frequency, snapped to the patch's tuning, or a -1 to 1 control spread over the
+        octave range. For the custom scale each twelfth of the interval graph is a semitone above
+        the root, drawn above the middle to include it. hysteresis is how many semitones closer
+        the value has to get to another note before the output moves, so it doesn't flicker."#
+                .to_string(),
+        inputs: BTreeMap::from([
+            (
+                "audio 1".to_string(),
+                InputParameter {
+                    data_type: DataType::AudioSource,
+                    kind: InputParamKind::ConnectionOnly,
+                    name: "audio 1".to_string(),
+                    value: InputValueConfig::AudioSource {},
+                },
+            ),
+            dropdown_input(
+                "root",
+                Note::C.to_string(),
+                Note::ALL.map(|x| x.to_string()).to_vec(),
+            ),
+            dropdown_input(
+                "scale",
+                SCALES[0].0.to_string(),
+                SCALES
+                    .iter()
+                    .map(|x| x.0)
+                    .chain([CUSTOM_SCALE])
+                    .map(|x| x.to_string())
+                    .collect(),
+            ),
+            (
+                "custom intervals".to_string(),
+                InputParameter {
+                    data_type: DataType::Graph,
+                    kind: InputParamKind::ConstantOnly,
+                    name: "custom intervals".to_string(),
+                    value: InputValueConfig::Graph {
+                        value: vec![1.0; WAVE_TABLE_SIZE],
+                        height: 100.0,
+                        width: 300.0,
+                    },
+                },
+            ),
+            dropdown_input(
+                "signal",
+                QUANTIZE_SIGNALS[0].to_string(),
+                QUANTIZE_SIGNALS.map(|x| x.to_string()).to_vec(),
+            ),
+            float_input("lowest octave", 0.0, 0.0, 8.0),
+            float_input("highest octave", 8.0, 0.0, 8.0),
+            float_input("hysteresis", 0.1, 0.0, 1.0),
+        ]),
+        outputs: BTreeMap::from([(
+            "out".to_string(),
+            Output {
+                data_type: DataType::AudioSource,
+                name: "out".to_string(),
+            },
+        )]),
+    }
+}
+
+/// Reads which semitones are on from the custom interval graph, one twelfth of it each.
+fn custom_mask(table: &[f32]) -> [bool; 12] {
+    let mut mask = [false; 12];
+    for (idx, on) in mask.iter_mut().enumerate() {
+        let sample = (idx * 2 + 1) * table.len() / 24;
+        *on = table.get(sample).map(|x| *x > 0.0).unwrap_or(false);
+    }
+    mask
+}
+
+pub fn quantize_to_scale_logic(mut props: SoundNodeProps) -> SoundNodeResult {
+    let cloned = props.clone_sound(props.get_source("audio 1")?)?;
+    let root = props.get_dropdown("root")?;
+    let root = Note::ALL
+        .iter()
+        .position(|x| x.to_string() == root)
+        .unwrap_or(0);
+    let scale = props.get_dropdown("scale")?;
+    let mask = match SCALES.iter().find(|x| x.0 == scale) {
+        Some((_, intervals)) => scale_mask(intervals),
+        None => custom_mask(
+            &props
+                .get_graph("custom intervals")?
+                .unwrap_or(vec![1.0; WAVE_TABLE_SIZE]),
+        ),
+    };
+    let signal = match props.get_dropdown("signal")?.as_str() {
+        "control" => QuantizeSignal::Control,
+        _ => QuantizeSignal::Frequency,
+    };
+    let octave = |x: f32| x.round().clamp(0.0, 8.0) as usize;
+    let octaves = (
+        octave(props.get_float("lowest octave")?),
+        octave(props.get_float("highest octave")?),
+    );
+    let quantizer = QuantizeToScale::new(
+        cloned,
+        signal,
+        mask,
+        root,
+        octaves,
+        props.get_float("hysteresis")?,
+        props.tuning(),
+    );
+    Ok(BTreeMap::from([(
+        "out".to_string(),
+        ValueType::AudioSource {
+            value: props.push_sound(Box::new(quantizer)),
+        },
+    )]))
+}
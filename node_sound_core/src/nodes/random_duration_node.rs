@@ -1,5 +1,5 @@
 use super::{SoundNodeProps, SoundNodeResult};
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use std::collections::BTreeMap;
 pub fn random_duration_node() -> SoundNode {
     SoundNode {
         name: "Random Take Duration".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Takes a snapshot of the waveform for the amount of time you input.
         The Random Take Duration node does this as a random number from min duration to max duration."#
             .to_string(),
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn ref_node() -> SoundNode {
     SoundNode {
         name: "Ref".to_string(),
+        category: NodeCategory::Utility,
         tooltip: r#"Does nothing to the sound itself,
 Copies the result of a sound and caches it for each sample the graph produces (this is good for performance)."#
             .to_string(),
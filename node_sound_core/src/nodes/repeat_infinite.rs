@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn repeat_infinite_node() -> SoundNode {
     SoundNode {
         name: "Repeat Infinite".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Repeats a given waveform infinitely if it stops."#.to_string(),
         inputs: BTreeMap::from([(
             "audio 1".to_string(),
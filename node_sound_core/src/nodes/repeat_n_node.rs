@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn repeat_n_node() -> SoundNode {
     SoundNode {
         name: "Repeat N".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"Repeats a given waveform a certain number of times."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -1,6 +1,6 @@
 use super::{SoundNodeProps, SoundNodeResult};
 use crate::constants::MAX_FREQ;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -13,6 +13,7 @@ use std::collections::BTreeMap;
 pub fn reverb_node() -> SoundNode {
     SoundNode {
         name: "Reverb".to_string(),
+        category: NodeCategory::Time,
         tooltip: r#"More like a delay, this is just a mix node, delay node, and amplify node under the hood."#
             .to_string(),
         inputs: BTreeMap::from([
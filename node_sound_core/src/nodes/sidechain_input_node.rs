@@ -0,0 +1,22 @@
+use crate::nodes::{NodeCategory, SoundNode};
+use crate::sounds::input::SIDECHAIN_BUS;
+use std::collections::BTreeMap;
+
+use super::input_node::{channel_input, input_output, input_source};
+use super::{SoundNodeProps, SoundNodeResult};
+
+pub fn sidechain_input_node() -> SoundNode {
+    SoundNode {
+        name: "Sidechain Input".to_string(),
+        category: NodeCategory::Sources,
+        tooltip: r#"Input sound from the effect's sidechain bus, for ducking, gating or following
+        another track. Silent if the DAW doesn't route anything to it."#
+            .to_string(),
+        inputs: BTreeMap::from([channel_input()]),
+        outputs: input_output(),
+    }
+}
+
+pub fn sidechain_input_logic(props: SoundNodeProps) -> SoundNodeResult {
+    input_source(props, SIDECHAIN_BUS)
+}
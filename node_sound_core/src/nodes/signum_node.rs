@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn signum_node() -> SoundNode {
     SoundNode {
         name: "Signum".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"if the wave is above 0, it becomes 1. if it's below zero, it becomes -1.
 This results in a weird square wave type effect."#
             .to_string(),
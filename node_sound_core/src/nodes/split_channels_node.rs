@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -10,6 +10,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn split_channels_node() -> SoundNode {
     SoundNode {
         name: "Split Channels".to_string(),
+        category: NodeCategory::Utility,
         tooltip: r#"Takes only the left or right channel and puts it on both channels."#
             .to_string(),
         inputs: BTreeMap::from([
@@ -1,5 +1,5 @@
 use crate::constants::MAX_FREQ;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn square_node() -> SoundNode {
     SoundNode {
         name: "Square Wave".to_string(),
+        category: NodeCategory::Oscillators,
         tooltip: r#"Square waveform generator."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -0,0 +1,149 @@
+use crate::constants::WAVE_TABLE_SIZE;
+use crate::nodes::{NodeCategory, SoundNode};
+use crate::sound_graph::graph_types::{
+    DataType, InputParameter, InputValueConfig, Output, ValueType,
+};
+use crate::sound_graph::note::NoteSpeed;
+use crate::sounds::step_sequencer::{SequencerStep, StepSequencer};
+use egui_node_graph_2::InputParamKind;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use super::{SoundNodeProps, SoundNodeResult};
+
+pub const NAME: &str = "Step Sequencer";
+const STEP_COUNTS: [&str; 3] = ["8", "16", "32"];
+
+fn graph_input(name: &str, value: f32) -> (String, InputParameter) {
+    (
+        name.to_string(),
+        InputParameter {
+            data_type: DataType::Graph,
+            kind: InputParamKind::ConstantOnly,
+            name: name.to_string(),
+            value: InputValueConfig::Graph {
+                value: vec![value; WAVE_TABLE_SIZE],
+                height: 100.0,
+                width: 300.0,
+            },
+        },
+    )
+}
+
+pub fn step_sequencer_node() -> SoundNode {
+    SoundNode {
+        name: NAME.to_string(),
+        category: NodeCategory::Sequencing,
+        tooltip: r#"Steps through 8, 16 or 32 values, one per note of the chosen speed. value holds
+        the current step's value and gate is 1 while the step plays, for modulating other nodes.
+        Draw the values, the chance each step's gate opens (bottom never, top always) and its
+        ratchets (bottom once, top 4 times) in the graphs, each step takes its share of the width."#
+            .to_string(),
+        inputs: BTreeMap::from([
+            (
+                "steps".to_string(),
+                InputParameter {
+                    data_type: DataType::Dropdown,
+                    kind: InputParamKind::ConstantOnly,
+                    name: "steps".to_string(),
+                    value: InputValueConfig::Dropdown {
+                        value: STEP_COUNTS[1].to_string(),
+                        values: STEP_COUNTS.map(|x| x.to_string()).to_vec(),
+                    },
+                },
+            ),
+            (
+                "note speed".to_string(),
+                InputParameter {
+                    data_type: DataType::Dropdown,
+                    kind: InputParamKind::ConstantOnly,
+                    name: "note speed".to_string(),
+                    value: InputValueConfig::Dropdown {
+                        value: NoteSpeed::Sixteenth.to_string(),
+                        values: NoteSpeed::ALL.map(|x| x.to_string()).to_vec(),
+                    },
+                },
+            ),
+            graph_input("values", 0.0),
+            graph_input("probability", 1.0),
+            graph_input("ratchets", -1.0),
+            (
+                "gate length".to_string(),
+                InputParameter {
+                    data_type: DataType::Float,
+                    kind: InputParamKind::ConnectionOrConstant,
+                    name: "gate length".to_string(),
+                    value: InputValueConfig::Float {
+                        value: 0.5,
+                        min: 0.0,
+                        max: 1.0,
+                    },
+                },
+            ),
+            (
+                "lock to host".to_string(),
+                InputParameter {
+                    data_type: DataType::Float,
+                    kind: InputParamKind::ConnectionOrConstant,
+                    name: "lock to host".to_string(),
+                    value: InputValueConfig::Bool { value: false },
+                },
+            ),
+        ]),
+        outputs: BTreeMap::from([
+            (
+                "value".to_string(),
+                Output {
+                    data_type: DataType::AudioSource,
+                    name: "value".to_string(),
+                },
+            ),
+            (
+                "gate".to_string(),
+                Output {
+                    data_type: DataType::AudioSource,
+                    name: "gate".to_string(),
+                },
+            ),
+        ]),
+    }
+}
+
+pub fn step_sequencer_logic(mut props: SoundNodeProps) -> SoundNodeResult {
+    let count = props.get_dropdown("steps")?.parse::<usize>()?;
+    let speed = NoteSpeed::from_str(&props.get_dropdown("note speed")?)?;
+    let table = |name: &str| {
+        props
+            .get_graph(name)
+            .map(|x| x.unwrap_or(vec![0.0; WAVE_TABLE_SIZE]))
+    };
+    let steps = SequencerStep::from_tables(
+        count,
+        &table("values")?,
+        &table("probability")?,
+        &table("ratchets")?,
+    );
+    let sequencer = StepSequencer::new(
+        props.sample_rate(),
+        props.bpm(),
+        speed,
+        props.note_speed(),
+        steps,
+        props.get_float("gate length")?,
+        props.host_sync(),
+    );
+    Ok(BTreeMap::from([
+        (
+            "gate".to_string(),
+            ValueType::AudioSource {
+                value: props.push_sound(Box::new(sequencer.clone().gate())),
+            },
+        ),
+        (
+            "value".to_string(),
+            ValueType::AudioSource {
+                value: props.push_sound(Box::new(sequencer)),
+            },
+        ),
+    ]))
+}
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn switch_node() -> SoundNode {
     SoundNode {
         name: "Switch".to_string(),
+        category: NodeCategory::Utility,
         tooltip: r#"Uses the switch channel to switch between two sources."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -1,5 +1,5 @@
 use crate::constants::WAVE_TABLE_SIZE;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -12,6 +12,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn tracker_node() -> SoundNode {
     SoundNode {
         name: "Tracker".to_string(),
+        category: NodeCategory::Sequencing,
         tooltip: r#"Allows you to play notes at given speeds."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -47,6 +48,15 @@ pub fn tracker_node() -> SoundNode {
                     },
                 },
             ),
+            (
+                "lock to host".to_string(),
+                InputParameter {
+                    data_type: DataType::Float,
+                    kind: InputParamKind::ConnectionOrConstant,
+                    name: "lock to host".to_string(),
+                    value: InputValueConfig::Bool { value: false },
+                },
+            ),
         ]),
         outputs: BTreeMap::from([(
             "out".to_string(),
@@ -63,16 +73,20 @@ pub fn tracker_logic(mut props: SoundNodeProps) -> SoundNodeResult {
     Ok(BTreeMap::from([(
         "out".to_string(),
         ValueType::AudioSource {
-            value: props.push_sound(Box::new(Tracker::new(
-                props.sample_rate(),
-                props.bpm(),
-                props.get_tracker("tracker")?,
-                cloned,
-                props.note_speed(),
-                props
-                    .get_graph("graph")?
-                    .unwrap_or(vec![0.0; WAVE_TABLE_SIZE]),
-            ))),
+            value: props.push_sound(Box::new(
+                Tracker::new(
+                    props.sample_rate(),
+                    props.bpm(),
+                    props.get_tracker("tracker")?,
+                    cloned,
+                    props.note_speed(),
+                    props
+                        .get_graph("graph")?
+                        .unwrap_or(vec![0.0; WAVE_TABLE_SIZE]),
+                    props.host_sync(),
+                )
+                .tuning(props.tuning()),
+            )),
         },
     )]))
 }
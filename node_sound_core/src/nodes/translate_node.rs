@@ -1,5 +1,5 @@
 use crate::constants::MAX_FREQ;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -12,6 +12,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn translate_node() -> SoundNode {
     SoundNode {
         name: "Translate Wave".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Takes a wave going from start min and start max and
 morphs its position to be within the range of end min and end max."#
             .to_string(),
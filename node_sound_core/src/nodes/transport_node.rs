@@ -0,0 +1,51 @@
+use crate::nodes::{NodeCategory, SoundNode};
+use crate::sound_graph::graph_types::{DataType, Output, ValueType};
+use crate::sounds::transport::{TransportSource, TransportValue};
+use std::collections::BTreeMap;
+
+use super::{SoundNodeProps, SoundNodeResult};
+
+const OUTPUTS: [(&str, TransportValue); 6] = [
+    ("beats", TransportValue::Beats),
+    ("bar", TransportValue::Bar),
+    ("beat phase", TransportValue::BeatPhase),
+    ("numerator", TransportValue::Numerator),
+    ("denominator", TransportValue::Denominator),
+    ("playing", TransportValue::Playing),
+];
+
+pub fn transport_node() -> SoundNode {
+    SoundNode {
+        name: "Host Transport".to_string(),
+        category: NodeCategory::Automation,
+        tooltip:
+            r#"The DAW's transport. beats is the song position in quarter notes, bar counts from 0,
+        beat phase goes from 0 to 1 over each beat of the time signature and playing is 1 while the
+        DAW plays."#
+                .to_string(),
+        inputs: BTreeMap::from([]),
+        outputs: BTreeMap::from(OUTPUTS.map(|(name, _)| {
+            (
+                name.to_string(),
+                Output {
+                    data_type: DataType::AudioSource,
+                    name: name.to_string(),
+                },
+            )
+        })),
+    }
+}
+
+pub fn transport_logic(mut props: SoundNodeProps) -> SoundNodeResult {
+    let mut outputs = BTreeMap::new();
+    for (name, value) in OUTPUTS {
+        let transport = props.transport();
+        outputs.insert(
+            name.to_string(),
+            ValueType::AudioSource {
+                value: props.push_sound(Box::new(TransportSource::new(transport, value))),
+            },
+        );
+    }
+    Ok(outputs)
+}
@@ -1,6 +1,6 @@
 use super::{SoundNodeProps, SoundNodeResult};
 use crate::constants::MAX_FREQ;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use std::collections::BTreeMap;
 pub fn unison_node() -> SoundNode {
     SoundNode {
         name: "Unison".to_string(),
+        category: NodeCategory::Oscillators,
         tooltip: r#"Unison with multiple voices.
 voices is the number of voices.
 unison is the amount of unison the voices have with them being identical at 0 and
@@ -1,5 +1,5 @@
 use crate::constants::WAVE_TABLE_SIZE;
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -12,6 +12,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn vertical_wave_shaper_node() -> SoundNode {
     SoundNode {
         name: "Vertical Wave Shaper".to_string(),
+        category: NodeCategory::Distortion,
         tooltip: r#"Shapes the incoming wave vertically by the graph."#.to_string(),
         inputs: BTreeMap::from([
             (
@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn wave_folder_node() -> SoundNode {
     SoundNode {
         name: "Wave Folder".to_string(),
+        category: NodeCategory::Distortion,
         tooltip: r#"Distortion effect for folding a wave into itself repeatedly."#.to_string(),
         inputs: BTreeMap::from([
             (
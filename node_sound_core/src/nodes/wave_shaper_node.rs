@@ -1,5 +1,5 @@
 use crate::constants::{MAX_FREQ, MIDDLE_C_FREQ, WAVE_TABLE_SIZE};
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn wave_shaper_node() -> SoundNode {
     SoundNode {
         name: "Wave Shaper".to_string(),
+        category: NodeCategory::Distortion,
         tooltip: r#"Shape a waveform manually."#.to_string(),
         inputs: BTreeMap::from([
             (
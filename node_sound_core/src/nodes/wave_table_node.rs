@@ -1,5 +1,5 @@
 use crate::constants::{MAX_FREQ, MIDDLE_C_FREQ};
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn wave_table_node() -> SoundNode {
     SoundNode {
         name: "Wave Table".to_string(),
+        category: NodeCategory::Oscillators,
         tooltip: r#"Takes a waveform and stores it in a table, interpolating values. can change frequency too."#
             .to_string(),
         inputs: BTreeMap::from([
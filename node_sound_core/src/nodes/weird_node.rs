@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn weird_node() -> SoundNode {
     SoundNode {
         name: "Weird".to_string(),
+        category: NodeCategory::Distortion,
         tooltip: r#"Weird effect that randomly cycles between 3 different wave shaping functions."#
             .to_string(),
         inputs: BTreeMap::from([(
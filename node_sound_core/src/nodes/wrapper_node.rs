@@ -1,4 +1,4 @@
-use crate::nodes::SoundNode;
+use crate::nodes::{NodeCategory, SoundNode};
 use crate::sound_graph::graph_types::{
     DataType, InputParameter, InputValueConfig, Output, ValueType,
 };
@@ -11,6 +11,7 @@ use super::{SoundNodeProps, SoundNodeResult};
 pub fn wrapper_node() -> SoundNode {
     SoundNode {
         name: "Wrapper".to_string(),
+        category: NodeCategory::Math,
         tooltip: r#"Takes the last value and adds the current value to it.
 wraps this value around to the other side if it exceeds -1.0 to 1.0."#
             .to_string(),
@@ -0,0 +1,255 @@
+use super::note::NoteSpeed;
+use eframe::egui::{self, ComboBox, DragValue};
+use serde::{Deserialize, Serialize};
+
+const MIDI_NOTES: usize = 128;
+pub const MAX_ARP_OCTAVES: u8 = 4;
+const RANDOM_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub enum ArpMode {
+    #[default]
+    Up,
+    Down,
+    UpDown,
+    Random,
+    AsPlayed,
+}
+
+impl ArpMode {
+    pub const ALL: [ArpMode; 5] = [
+        ArpMode::Up,
+        ArpMode::Down,
+        ArpMode::UpDown,
+        ArpMode::Random,
+        ArpMode::AsPlayed,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArpMode::Up => "up",
+            ArpMode::Down => "down",
+            ArpMode::UpDown => "up-down",
+            ArpMode::Random => "random",
+            ArpMode::AsPlayed => "as played",
+        }
+    }
+}
+
+/// The synth's arpeggiator settings. The plugin exposes them as host parameters, and they are
+/// saved with the patch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArpSettings {
+    pub enabled: bool,
+    pub mode: ArpMode,
+    pub octaves: u8,
+    pub rate: NoteSpeed,
+    /// How much of each step the note is held for, 0 to 1.
+    pub gate: f32,
+    /// Keeps playing the last chord after the keys are let go, until a new one is played.
+    pub latch: bool,
+    /// Set when a patch is loaded, so the plugin knows to copy these into its parameters.
+    #[serde(skip)]
+    pub loaded: bool,
+}
+
+impl Default for ArpSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: ArpMode::Up,
+            octaves: 1,
+            rate: NoteSpeed::Sixteenth,
+            gate: 0.5,
+            latch: false,
+            loaded: false,
+        }
+    }
+}
+
+/// What the arpeggiator wants the synth to do this sample. A note ending and the next one
+/// starting can happen on the same sample.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ArpEvents {
+    pub note_off: Option<(u8, u8)>,
+    pub note_on: Option<(u8, u8, f32)>,
+}
+
+/// Turns the held notes into a sequence of single notes. Everything is preallocated, so it can
+/// run on the audio thread.
+pub struct Arpeggiator {
+    /// Held notes as `(channel, note, velocity)`, in the order they were played.
+    held: Vec<(u8, u8, f32)>,
+    pattern: Vec<(u8, u8, f32)>,
+    keys_down: usize,
+    /// Whether the held notes are only kept because of latch, so the next key starts a new chord.
+    latched: bool,
+    beats: f64,
+    step: Option<u64>,
+    position: usize,
+    playing: Option<(u8, u8)>,
+    /// Xorshift state for the random mode, which can't lock the thread's generator on the audio
+    /// thread.
+    random: u64,
+}
+
+impl Default for Arpeggiator {
+    fn default() -> Self {
+        Self {
+            held: Vec::with_capacity(MIDI_NOTES),
+            pattern: Vec::with_capacity(MIDI_NOTES * 2 * MAX_ARP_OCTAVES as usize),
+            keys_down: 0,
+            latched: false,
+            beats: 0.0,
+            step: None,
+            position: 0,
+            playing: None,
+            random: RANDOM_SEED,
+        }
+    }
+}
+
+impl Arpeggiator {
+    pub fn note_on(&mut self, channel: u8, note: u8, velocity: f32) {
+        if self.latched {
+            self.held.clear();
+            self.latched = false;
+        }
+        if self.held.is_empty() {
+            // The first note of a new chord starts the pattern straight away.
+            self.beats = 0.0;
+            self.step = None;
+            self.position = 0;
+        }
+        self.keys_down += 1;
+        self.held.retain(|x| x.1 != note);
+        if self.held.len() < MIDI_NOTES {
+            self.held.push((channel, note, velocity));
+        }
+    }
+
+    pub fn note_off(&mut self, note: u8, latch: bool) {
+        self.keys_down = self.keys_down.saturating_sub(1);
+        if latch {
+            self.latched = self.keys_down == 0;
+        } else {
+            self.held.retain(|x| x.1 != note);
+        }
+    }
+
+    /// Drops every held note, for when the arpeggiator is turned off.
+    pub fn clear(&mut self) {
+        self.held.clear();
+        self.keys_down = 0;
+        self.latched = false;
+    }
+
+    /// A random index below `len`, which has to be more than zero.
+    fn random_index(&mut self, len: usize) -> usize {
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 7;
+        self.random ^= self.random << 17;
+        (self.random % len as u64) as usize
+    }
+
+    fn build_pattern(&mut self, settings: &ArpSettings) {
+        self.pattern.clear();
+        for octave in 0..settings.octaves.clamp(1, MAX_ARP_OCTAVES) {
+            for (channel, note, velocity) in self.held.iter() {
+                let note = *note as usize + octave as usize * 12;
+                if note < MIDI_NOTES {
+                    self.pattern.push((*channel, note as u8, *velocity));
+                }
+            }
+        }
+        // The unstable sorts don't allocate.
+        match settings.mode {
+            ArpMode::AsPlayed | ArpMode::Random => {}
+            ArpMode::Up | ArpMode::UpDown => self.pattern.sort_unstable_by_key(|x| x.1),
+            ArpMode::Down => self
+                .pattern
+                .sort_unstable_by_key(|x| std::cmp::Reverse(x.1)),
+        }
+        if settings.mode == ArpMode::UpDown && self.pattern.len() > 2 {
+            for idx in (1..self.pattern.len() - 1).rev() {
+                self.pattern.push(self.pattern[idx]);
+            }
+        }
+    }
+
+    /// Moves the arpeggiator on by one sample of `beats_per_sample` quarter notes.
+    pub fn tick(&mut self, settings: &ArpSettings, beats_per_sample: f64) -> ArpEvents {
+        let mut events = ArpEvents::default();
+        if !settings.enabled {
+            self.clear();
+        }
+        if !settings.latch && self.latched {
+            self.held.clear();
+            self.latched = false;
+        }
+        if self.held.is_empty() {
+            events.note_off = self.playing.take();
+            self.step = None;
+            return events;
+        }
+        let position = self.beats / settings.rate.get_beats() as f64;
+        let step = position.floor() as u64;
+        if self.step != Some(step) {
+            self.step = Some(step);
+            events.note_off = self.playing.take();
+            self.build_pattern(settings);
+            if !self.pattern.is_empty() {
+                let idx = match settings.mode {
+                    ArpMode::Random => self.random_index(self.pattern.len()),
+                    _ => self.position % self.pattern.len(),
+                };
+                self.position = self.position.wrapping_add(1);
+                let (channel, note, velocity) = self.pattern[idx];
+                events.note_on = Some((channel, note, velocity));
+                self.playing = Some((channel, note));
+            }
+        } else if position.fract() >= settings.gate.clamp(0.01, 1.0) as f64 {
+            events.note_off = self.playing.take();
+        }
+        self.beats += beats_per_sample;
+        events
+    }
+}
+
+impl ArpSettings {
+    /// Shows the settings other than the on switch, returns whether any changed.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let before = self.clone();
+        egui::Grid::new("arpeggiator_settings").show(ui, |ui| {
+            ui.label("mode");
+            ComboBox::from_id_salt("arpeggiator_mode")
+                .selected_text(self.mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in ArpMode::ALL {
+                        ui.selectable_value(&mut self.mode, mode, mode.name());
+                    }
+                });
+            ui.end_row();
+            ui.label("octaves");
+            ui.add(DragValue::new(&mut self.octaves).range(1..=MAX_ARP_OCTAVES));
+            ui.end_row();
+            ui.label("rate");
+            ComboBox::from_id_salt("arpeggiator_rate")
+                .selected_text(self.rate.to_string())
+                .show_ui(ui, |ui| {
+                    for rate in NoteSpeed::ALL {
+                        let name = rate.to_string();
+                        ui.selectable_value(&mut self.rate, rate, name);
+                    }
+                });
+            ui.end_row();
+            ui.label("gate");
+            ui.add(DragValue::new(&mut self.gate).range(0.01..=1.0).speed(0.01));
+            ui.end_row();
+            ui.label("latch");
+            ui.checkbox(&mut self.latch, "");
+            ui.end_row();
+        });
+        *self != before
+    }
+}
@@ -0,0 +1,155 @@
+use super::graph::{ActiveNodeState, FileManager, NodeId, SoundNodeGraphState, evaluate_node};
+use super::graph_types::ValueType;
+use crate::sound_map::{DawSource, GenericSource};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// A single node's output played on its own, started from the beginning.
+pub struct Audition {
+    source: GenericSource,
+    index: usize,
+}
+
+impl Audition {
+    pub fn new(source: GenericSource) -> Self {
+        Self { source, index: 0 }
+    }
+
+    pub fn next(&mut self) -> (f32, f32) {
+        let index = self.index as f32;
+        self.index += 1;
+        (
+            self.source
+                .next(index, 0)
+                .unwrap_or_default()
+                .clamp(-1.0, 1.0),
+            self.source
+                .next(index, 1)
+                .unwrap_or_default()
+                .clamp(-1.0, 1.0),
+        )
+    }
+}
+
+pub type SharedAudition = Arc<Mutex<Option<Audition>>>;
+
+/// Evaluates `node_id` on its own at the test pitch, a note speed of 1. The queue is cleared
+/// again afterwards, the returned source doesn't depend on it.
+pub fn render_node_source(
+    state: &mut SoundNodeGraphState,
+    node_id: NodeId,
+) -> Result<GenericSource, Box<dyn std::error::Error>> {
+    let note_speed = state._unserializeable_state.queue.get_note_speed();
+    state._unserializeable_state.queue.set_note_speed(1.0);
+    let graph = state.editor_state.graph.clone();
+    let definitions = state._unserializeable_state.node_definitions.clone();
+    let result = evaluate_node(&graph, node_id, &mut HashMap::new(), &definitions, state);
+    let sound = match result {
+        Ok(ValueType::AudioSource { value }) => {
+            state._unserializeable_state.queue.clone_sound(value)
+        }
+        Ok(_) => Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Node has no audio output",
+        )) as Box<dyn std::error::Error>),
+        Err(err) => Err(err),
+    };
+    state._unserializeable_state.queue.clear();
+    state
+        ._unserializeable_state
+        .queue
+        .set_note_speed(note_speed);
+    sound
+}
+
+/// Renders `seconds` of `source` to a stereo 32 bit float WAV file. Long recordings take a
+/// while, so call it from a background thread.
+pub fn record_source(
+    source: GenericSource,
+    sample_rate: f32,
+    path: &Path,
+    seconds: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut audition = Audition::new(source);
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: sample_rate as u32,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for _ in 0..(seconds as f32 * sample_rate) as usize {
+        let (left, right) = audition.next();
+        writer.write_sample(left)?;
+        writer.write_sample(right)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+impl FileManager {
+    /// Asks where to save a recording that was requested from a node's Record button.
+    pub fn open_record_dialog(&mut self) {
+        if let Some(node_id) = self.record_active {
+            self.record_path = rfd::FileDialog::new()
+                .add_filter("audio", &["wav"])
+                .set_file_name("recording.wav")
+                .save_file()
+                .map(|x| (x, node_id));
+            self.record_active = None;
+        }
+    }
+}
+
+/// Evaluates the node being recorded once its file dialog has closed, and renders it on a
+/// background thread that leaves its result in the `FileManager`.
+pub fn handle_recording(state: &mut SoundNodeGraphState) {
+    let finished = match state.user_state.files.try_lock() {
+        Ok(mut files) => files.record_result.take(),
+        Err(_x) => None,
+    };
+    match finished {
+        Some(Ok(_)) => state._unserializeable_state.error = None,
+        Some(Err(err)) => {
+            state._unserializeable_state.error = Some(format!("Recording failed: {}", err))
+        }
+        None => {}
+    }
+    let node_id = match state.user_state.active_node {
+        ActiveNodeState::RecordingNode(x) => x,
+        _ => return,
+    };
+    let path = match state.user_state.files.try_lock() {
+        Ok(mut files) => {
+            if files.record_active.is_some() {
+                return;
+            }
+            files.record_path.take()
+        }
+        Err(_x) => return,
+    };
+    state.user_state.active_node = ActiveNodeState::NoNode;
+    if let Some((path, record_node_id)) = path {
+        if record_node_id != node_id {
+            return;
+        }
+        let seconds = state.user_state.recording_length.max(1);
+        let sample_rate = state._unserializeable_state.queue.get_sample_rate();
+        let source = match render_node_source(state, node_id) {
+            Ok(x) => x,
+            Err(err) => {
+                state._unserializeable_state.error = Some(format!("Recording failed: {}", err));
+                return;
+            }
+        };
+        let files = state.user_state.files.clone();
+        std::thread::spawn(move || {
+            let result =
+                record_source(source, sample_rate, &path, seconds).map_err(|x| x.to_string());
+            if let Ok(mut files) = files.lock() {
+                files.record_result = Some(result);
+            }
+        });
+    }
+}
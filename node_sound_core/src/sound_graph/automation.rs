@@ -0,0 +1,397 @@
+use super::graph::{NodeData, SoundNodeGraph, SoundNodeGraphState};
+use super::graph_types::{DataType, ValueType};
+use eframe::egui::{self, ComboBox, DragValue};
+use egui_node_graph_2::Graph;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// The number of automation parameters the plugins expose to the host.
+pub const AUTOMATION_COUNT: usize = 18;
+
+/// How an automation parameter moves towards a new value the host sets. The plugins turn this
+/// into the smoothing style of the parameter's smoother.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub enum AutomationSmoothing {
+    #[default]
+    None,
+    /// Reaches the new value in a straight line over this many milliseconds.
+    Linear(f32),
+    /// Gets most of the way to the new value over this many milliseconds.
+    Exponential(f32),
+}
+
+impl AutomationSmoothing {
+    const DEFAULT_MS: f32 = 10.0;
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AutomationSmoothing::None => "none",
+            AutomationSmoothing::Linear(_) => "linear",
+            AutomationSmoothing::Exponential(_) => "exponential",
+        }
+    }
+
+    fn ms(&self) -> Option<f32> {
+        match self {
+            AutomationSmoothing::None => None,
+            AutomationSmoothing::Linear(x) | AutomationSmoothing::Exponential(x) => Some(*x),
+        }
+    }
+
+    /// Shows the smoothing mode and time, returns whether either changed.
+    pub fn ui(&mut self, ui: &mut egui::Ui, id: usize) -> bool {
+        let before = *self;
+        let ms = self.ms().unwrap_or(Self::DEFAULT_MS);
+        ComboBox::from_id_salt(format!("automation_smoothing_{}", id))
+            .selected_text(self.name())
+            .width(80.0)
+            .show_ui(ui, |ui| {
+                for option in [
+                    AutomationSmoothing::None,
+                    AutomationSmoothing::Linear(ms),
+                    AutomationSmoothing::Exponential(ms),
+                ] {
+                    ui.selectable_value(self, option, option.name());
+                }
+            });
+        match self {
+            AutomationSmoothing::None => {}
+            AutomationSmoothing::Linear(x) | AutomationSmoothing::Exponential(x) => {
+                ui.add(DragValue::new(x).range(0.0..=1000.0).suffix(" ms"));
+            }
+        }
+        *self != before
+    }
+}
+
+/// A user named automation parameter. The host sees it as the parameter at the same index, which
+/// keeps its fixed A1 to A18 name, with the macro's name only showing in the value string. Its
+/// -1 to 1 value is mapped onto the macro's range before it reaches the graph.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AutomationMacro {
+    pub name: String,
+    pub min: f32,
+    pub max: f32,
+    pub unit: String,
+    #[serde(default)]
+    pub smoothing: AutomationSmoothing,
+}
+
+impl AutomationMacro {
+    pub fn new(idx: usize) -> Self {
+        Self {
+            name: format!("A{}", idx + 1),
+            min: -1.0,
+            max: 1.0,
+            unit: String::new(),
+            smoothing: AutomationSmoothing::None,
+        }
+    }
+
+    pub fn format(&self, value: f32) -> String {
+        let value = MacroSnapshot::from(self).map(value);
+        match self.unit.trim() {
+            "" => format!("{:.2}", value),
+            unit => format!("{:.2} {}", value, unit),
+        }
+    }
+
+    /// Reads a value typed in the macro's range and unit back into the -1 to 1 host value.
+    pub fn parse(&self, text: &str) -> Option<f32> {
+        let value = text
+            .trim()
+            .trim_end_matches(self.unit.trim())
+            .trim()
+            .parse::<f32>()
+            .ok()?;
+        if self.max == self.min {
+            return Some(0.0);
+        }
+        Some(((value - self.min) / (self.max - self.min) * 2.0 - 1.0).clamp(-1.0, 1.0))
+    }
+}
+
+/// The parts of a macro the audio thread needs, small enough to copy without allocating.
+#[derive(Clone, Copy, Debug)]
+pub struct MacroSnapshot {
+    pub min: f32,
+    pub max: f32,
+    pub smoothing: AutomationSmoothing,
+}
+
+impl Default for MacroSnapshot {
+    fn default() -> Self {
+        Self {
+            min: -1.0,
+            max: 1.0,
+            smoothing: AutomationSmoothing::None,
+        }
+    }
+}
+
+impl From<&AutomationMacro> for MacroSnapshot {
+    fn from(value: &AutomationMacro) -> Self {
+        Self {
+            min: value.min,
+            max: value.max,
+            smoothing: value.smoothing,
+        }
+    }
+}
+
+impl MacroSnapshot {
+    /// Maps a host value in -1 to 1 onto the macro's range.
+    #[inline]
+    pub fn map(&self, value: f32) -> f32 {
+        self.min + (value + 1.0) * 0.5 * (self.max - self.min)
+    }
+}
+
+/// The macros in use, one per host automation parameter from the first onwards.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AutomationMacros {
+    macros: Vec<AutomationMacro>,
+    #[serde(skip)]
+    pub open: bool,
+    /// The macro whose name is being typed and the text so far.
+    #[serde(skip)]
+    editing: Option<(usize, String)>,
+    #[serde(skip)]
+    rename_error: Option<String>,
+}
+
+impl Default for AutomationMacros {
+    fn default() -> Self {
+        Self {
+            macros: (0..AUTOMATION_COUNT).map(AutomationMacro::new).collect(),
+            open: false,
+            editing: None,
+            rename_error: None,
+        }
+    }
+}
+
+impl AutomationMacros {
+    pub fn len(&self) -> usize {
+        self.macros.len()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&AutomationMacro> {
+        self.macros.get(idx)
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut AutomationMacro> {
+        self.macros.get_mut(idx)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.macros.iter().map(|x| &x.name)
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.macros.iter().position(|x| x.name == name)
+    }
+
+    pub fn snapshot(&self) -> [MacroSnapshot; AUTOMATION_COUNT] {
+        let mut snapshot = [MacroSnapshot::default(); AUTOMATION_COUNT];
+        for (slot, automation_macro) in snapshot.iter_mut().zip(self.macros.iter()) {
+            *slot = automation_macro.into();
+        }
+        snapshot
+    }
+
+    /// The value string the host shows for the parameter at `idx`.
+    pub fn format_value(&self, idx: usize, value: f32) -> String {
+        match self.get(idx) {
+            Some(x) => format!("{}: {}", x.name, x.format(value)),
+            None => format!("{:.2}", value),
+        }
+    }
+
+    pub fn parse_value(&self, idx: usize, text: &str) -> Option<f32> {
+        let text = text.trim();
+        match self.get(idx) {
+            Some(x) => x.parse(text.strip_prefix(&format!("{}:", x.name)).unwrap_or(text)),
+            None => text.parse::<f32>().ok(),
+        }
+    }
+}
+
+/// Formats a host parameter through the macro at `idx`, for use as the parameter's value to
+/// string function. Falls back to the plain value while the editor holds the graph.
+pub fn macro_value_to_string(
+    graph: Arc<Mutex<SoundNodeGraph>>,
+    idx: usize,
+) -> Arc<dyn Fn(f32) -> String + Send + Sync> {
+    Arc::new(move |value| match graph.try_lock() {
+        Ok(x) => x
+            .state
+            .user_state
+            .automation_macros
+            .format_value(idx, value),
+        Err(_x) => format!("{:.2}", value),
+    })
+}
+
+pub fn macro_string_to_value(
+    graph: Arc<Mutex<SoundNodeGraph>>,
+    idx: usize,
+) -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
+    Arc::new(move |text| match graph.try_lock() {
+        Ok(x) => x.state.user_state.automation_macros.parse_value(idx, text),
+        Err(_x) => text.trim().parse::<f32>().ok(),
+    })
+}
+
+/// Points the macro inputs of `graph` and every group inside it at the renamed macro.
+fn rename_macro(graph: &mut Graph<NodeData, DataType, ValueType>, old: &str, new: &str) {
+    for (_, input) in graph.inputs.iter_mut() {
+        match &mut input.value {
+            ValueType::Macro { value } if value == old => *value = new.to_string(),
+            _ => {}
+        }
+    }
+    for (_, node) in graph.nodes.iter_mut() {
+        if let Some(group) = &mut node.user_data.group {
+            rename_macro(&mut group.graph, old, new);
+        }
+    }
+}
+
+/// Whether any macro input of `graph` or a group inside it is bound to the macro `name`.
+fn macro_in_use(graph: &Graph<NodeData, DataType, ValueType>, name: &str) -> bool {
+    graph
+        .inputs
+        .iter()
+        .any(|(_, input)| matches!(&input.value, ValueType::Macro { value } if value == name))
+        || graph
+            .nodes
+            .iter()
+            .any(|(_, node)| match &node.user_data.group {
+                Some(group) => macro_in_use(&group.graph, name),
+                None => false,
+            })
+}
+
+/// The window for naming the macros and setting their ranges, units and smoothing. Names are
+/// only applied once the edit is finished, so the text in between never rebinds any nodes.
+pub fn macros_window(ctx: &egui::Context, state: &mut SoundNodeGraphState) {
+    let mut open = state.user_state.automation_macros.open;
+    if !open {
+        return;
+    }
+    // The graphs the open group sits in are swapped out while it's being edited.
+    let last_in_use = match state.user_state.automation_macros.macros.last() {
+        Some(x) => {
+            macro_in_use(&state.editor_state.graph, &x.name)
+                || state
+                    .group_stack
+                    .iter()
+                    .any(|frame| macro_in_use(&frame.parent.graph, &x.name))
+        }
+        None => false,
+    };
+    let mut changed = false;
+    let mut renames = vec![];
+    egui::Window::new("Automation macros")
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let macros = &mut state.user_state.automation_macros;
+            let names = macros.names().cloned().collect::<Vec<_>>();
+            let mut editing = macros.editing.take();
+            egui::Grid::new("automation_macros").show(ui, |ui| {
+                ui.label("");
+                ui.label("name");
+                ui.label("min");
+                ui.label("max");
+                ui.label("unit");
+                ui.label("smoothing");
+                ui.end_row();
+                for (idx, automation_macro) in macros.macros.iter_mut().enumerate() {
+                    ui.label(format!("A{}", idx + 1));
+                    let mut name = match &editing {
+                        Some((x, name)) if *x == idx => name.clone(),
+                        _ => automation_macro.name.clone(),
+                    };
+                    let response =
+                        ui.add(egui::TextEdit::singleline(&mut name).desired_width(100.0));
+                    if response.changed() {
+                        editing = Some((idx, name.clone()));
+                    }
+                    if response.lost_focus() && editing.as_ref().is_some_and(|x| x.0 == idx) {
+                        editing = None;
+                        let name = name.trim().to_string();
+                        if name.is_empty() || name == automation_macro.name {
+                            macros.rename_error = None;
+                        } else if names.contains(&name) {
+                            macros.rename_error = Some(format!("{} is already in use", name));
+                        } else {
+                            macros.rename_error = None;
+                            renames.push((automation_macro.name.clone(), name.clone()));
+                            automation_macro.name = name;
+                        }
+                    }
+                    changed |= ui
+                        .add(DragValue::new(&mut automation_macro.min).speed(0.01))
+                        .changed();
+                    changed |= ui
+                        .add(DragValue::new(&mut automation_macro.max).speed(0.01))
+                        .changed();
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut automation_macro.unit)
+                                .desired_width(40.0),
+                        )
+                        .changed();
+                    ui.horizontal(|ui| {
+                        changed |= automation_macro.smoothing.ui(ui, idx);
+                    });
+                    ui.end_row();
+                }
+            });
+            macros.editing = editing;
+            ui.horizontal(|ui| {
+                if macros.len() < AUTOMATION_COUNT && ui.button("+").clicked() {
+                    let mut automation_macro = AutomationMacro::new(macros.len());
+                    let mut n = macros.len() + 1;
+                    while names.contains(&automation_macro.name) {
+                        n += 1;
+                        automation_macro.name = format!("A{}", n);
+                    }
+                    macros.macros.push(automation_macro);
+                    changed = true;
+                }
+                if macros.len() > 1
+                    && ui
+                        .add_enabled(!last_in_use, egui::Button::new("−"))
+                        .on_disabled_hover_text("The last macro is still used by a node")
+                        .clicked()
+                {
+                    macros.macros.pop();
+                    changed = true;
+                }
+            });
+            if let Some(err) = &macros.rename_error {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+            if (1..names.len()).any(|i| names[..i].contains(&names[i])) {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "Macro names should be unique, nodes use the first match",
+                );
+            }
+            ui.label(
+                "The host lists the parameters as A1 to A18, a macro's name shows in its value.",
+            );
+        });
+    for (old, new) in renames.iter() {
+        rename_macro(&mut state.editor_state.graph, old, new);
+        for frame in state.group_stack.iter_mut() {
+            rename_macro(&mut frame.parent.graph, old, new);
+        }
+    }
+    state.user_state.automation_macros.open = open;
+    if changed || !renames.is_empty() {
+        state.user_state.is_saved = false;
+    }
+}
@@ -0,0 +1,140 @@
+use super::copy_paste_del_helpers::{ClipboardData, copy, delete_nodes, paste};
+use super::graph::SoundNodeGraphState;
+use eframe::egui::{self, Key, Modifiers, Vec2};
+use futures::executor;
+
+const PASTE_OFFSET: Vec2 = Vec2 { x: 30.0, y: 30.0 };
+
+/// The system clipboard, falling back to a clipboard internal to the editor when it can't be
+/// opened, e.g. on headless or some Wayland setups.
+#[derive(Default)]
+pub struct Clipboard {
+    internal: Option<String>,
+}
+
+impl Clipboard {
+    pub fn set(&mut self, text: String) {
+        if let Ok(mut clipboard) = arboard::Clipboard::new() {
+            let _ = clipboard.set().text(text.clone());
+        }
+        self.internal = Some(text);
+    }
+
+    pub fn get(&mut self) -> Option<String> {
+        match arboard::Clipboard::new().and_then(|mut x| x.get().text()) {
+            Ok(x) => Some(x),
+            Err(_x) => self.internal.clone(),
+        }
+    }
+}
+
+fn parse_clipboard(text: &str) -> Result<ClipboardData, String> {
+    let data: ClipboardData = match ron::de::from_str(text) {
+        Ok(x) => x,
+        Err(_x) => return Err("Clipboard does not contain nodes".to_string()),
+    };
+    data.validate()?;
+    Ok(data)
+}
+
+fn paste_data(state: &mut SoundNodeGraphState, offset: Vec2, data: ClipboardData) {
+    state.editor_state.selected_nodes.clear();
+    executor::block_on(paste(&mut state.editor_state, Some(offset), data));
+    state.user_state.is_saved = false;
+}
+
+pub fn copy_selected(state: &mut SoundNodeGraphState) {
+    if state.editor_state.selected_nodes.is_empty() {
+        return;
+    }
+    let data = copy(&mut state.editor_state, false);
+    match ron::ser::to_string(&data) {
+        Ok(x) => state._unserializeable_state.clipboard.set(x),
+        Err(err) => state._unserializeable_state.error = Some(format!("Copy failed: {}", err)),
+    }
+}
+
+pub fn cut_selected(state: &mut SoundNodeGraphState) {
+    copy_selected(state);
+    delete_selected(state);
+}
+
+pub fn delete_selected(state: &mut SoundNodeGraphState) {
+    delete_nodes(&mut state.editor_state, false);
+    state.editor_state.selected_nodes.clear();
+    state.user_state.is_saved = false;
+}
+
+/// Pastes `text`, or the clipboard contents if it is `None`, reporting anything that isn't a
+/// valid copy of nodes as an error rather than failing.
+pub fn paste_clipboard(state: &mut SoundNodeGraphState, offset: Vec2, text: Option<String>) {
+    let text = match text
+        .filter(|x| !x.is_empty())
+        .or_else(|| state._unserializeable_state.clipboard.get())
+    {
+        Some(x) => x,
+        None => {
+            state._unserializeable_state.error = Some("Clipboard is empty".to_string());
+            return;
+        }
+    };
+    match parse_clipboard(&text) {
+        Ok(data) => {
+            paste_data(state, offset, data);
+            state._unserializeable_state.error = None;
+        }
+        Err(err) => state._unserializeable_state.error = Some(format!("Paste failed: {}", err)),
+    }
+}
+
+/// Copies the selected nodes and pastes them slightly offset, without touching the clipboard.
+pub fn duplicate_selected(state: &mut SoundNodeGraphState) {
+    if state.editor_state.selected_nodes.is_empty() {
+        return;
+    }
+    let data = copy(&mut state.editor_state, false);
+    paste_data(state, PASTE_OFFSET, data);
+}
+
+/// Handles the copy, cut, paste, duplicate and delete shortcuts, leaving them to text fields
+/// that have focus. Depending on the integration copy, cut and paste arrive either as clipboard
+/// events or as plain key presses, so both are handled.
+pub fn handle_shortcuts(ctx: &egui::Context, state: &mut SoundNodeGraphState) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+    let (mut copy, mut cut, mut paste) = (false, false, None);
+    ctx.input(|i| {
+        for event in i.events.iter() {
+            match event {
+                egui::Event::Copy => copy = true,
+                egui::Event::Cut => cut = true,
+                egui::Event::Paste(text) => paste = Some(Some(text.clone())),
+                _ => {}
+            }
+        }
+    });
+    ctx.input_mut(|i| {
+        copy |= i.consume_key(Modifiers::COMMAND, Key::C);
+        cut |= i.consume_key(Modifiers::COMMAND, Key::X);
+        if i.consume_key(Modifiers::COMMAND, Key::V) && paste.is_none() {
+            paste = Some(None);
+        }
+    });
+
+    if copy {
+        copy_selected(state);
+    }
+    if cut {
+        cut_selected(state);
+    }
+    if let Some(text) = paste {
+        paste_clipboard(state, PASTE_OFFSET, text);
+    }
+    if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::D)) {
+        duplicate_selected(state);
+    }
+    if ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Delete)) {
+        delete_selected(state);
+    }
+}
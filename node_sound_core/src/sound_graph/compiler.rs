@@ -0,0 +1,201 @@
+use super::graph::{SoundNodeGraph, SoundNodeGraphState, evaluate_node};
+use super::graph_types::ValueType;
+use crate::sound_map::{AtomicF32, GenericSource};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender, TryRecvError, channel};
+use std::time::{Duration, Instant};
+
+/// How long the graph has to stay unchanged before it is re-evaluated.
+pub const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// The evaluated output for each requested key, usually a MIDI note.
+pub type CompileResult = Result<Vec<(usize, GenericSource)>, String>;
+
+/// A job number, the graph to evaluate and the `(key, note speed)` pairs to evaluate it at.
+type CompileJob = (usize, SoundNodeGraph, Vec<(usize, f32)>);
+
+/// The thread evaluations run on. Jobs that are queued behind a newer one are skipped.
+struct CompileWorker {
+    jobs: Sender<CompileJob>,
+    results: Receiver<(usize, CompileResult)>,
+}
+
+impl CompileWorker {
+    fn spawn() -> Self {
+        let (jobs, job_receiver) = channel::<CompileJob>();
+        let (result_sender, results) = channel();
+        std::thread::spawn(move || {
+            // Ends once the compiler is dropped and the job sender with it.
+            while let Ok(mut job) = job_receiver.recv() {
+                while let Ok(newer) = job_receiver.try_recv() {
+                    job = newer;
+                }
+                let (job_id, graph, notes) = job;
+                if result_sender
+                    .send((job_id, compile(graph, &notes)))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+        Self { jobs, results }
+    }
+}
+
+/// Re-evaluates the patch on a background thread whenever the graph changes, so the editor never
+/// waits on it. Changes are picked up through the history revision and debounced.
+#[derive(Default)]
+pub struct Compiler {
+    worker: Option<CompileWorker>,
+    /// The job number and graph revision of the newest job, until its result comes back.
+    pending: Option<(usize, usize)>,
+    next_job: usize,
+    compiled_revision: Option<usize>,
+    seen_revision: Option<usize>,
+    seen_at: Option<Instant>,
+}
+
+impl Compiler {
+    pub fn is_running(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Whether the last evaluation is of the graph at `revision` and nothing is running.
+    pub fn is_idle(&self, revision: usize) -> bool {
+        !self.is_running() && self.compiled_revision == Some(revision)
+    }
+
+    /// Forces the next call to `should_compile` to start a new evaluation, dropping the result
+    /// of any that is running.
+    pub fn invalidate(&mut self) {
+        self.pending = None;
+        self.compiled_revision = None;
+        self.seen_at = None;
+    }
+
+    /// Whether the graph at `revision` is out of date with the last evaluation, isn't already
+    /// being evaluated, and has stopped changing for long enough.
+    pub fn should_compile(&mut self, revision: usize) -> bool {
+        if self.seen_revision != Some(revision) {
+            let debounce = self.compiled_revision.is_some();
+            self.seen_revision = Some(revision);
+            self.seen_at = debounce.then(Instant::now);
+        }
+        self.pending.map(|x| x.1) != Some(revision)
+            && self.compiled_revision != Some(revision)
+            && self
+                .seen_at
+                .map(|x| x.elapsed() >= DEBOUNCE)
+                .unwrap_or(true)
+    }
+
+    /// Evaluates the output node of `job` once per `(key, note speed)` pair on the background
+    /// thread. A job still waiting when a newer one arrives is skipped, and the result of one
+    /// that was superseded while running is dropped.
+    pub fn start(&mut self, job: SoundNodeGraph, revision: usize, notes: Vec<(usize, f32)>) {
+        let job_id = self.next_job;
+        self.next_job = self.next_job.wrapping_add(1);
+        let worker = self.worker.get_or_insert_with(CompileWorker::spawn);
+        if let Err(err) = worker.jobs.send((job_id, job, notes)) {
+            // The thread panicked in an evaluation, so start a new one.
+            let worker = self.worker.insert(CompileWorker::spawn());
+            let _ = worker.jobs.send(err.0);
+        }
+        self.pending = Some((job_id, revision));
+    }
+
+    /// Returns the result of the newest evaluation once it has finished.
+    pub fn try_finish(&mut self) -> Option<CompileResult> {
+        let worker = self.worker.as_ref()?;
+        let mut finished = None;
+        loop {
+            match worker.results.try_recv() {
+                Ok((job_id, result)) if Some(job_id) == self.pending.map(|x| x.0) => {
+                    finished = Some(result)
+                }
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.worker = None;
+                    if self.pending.is_some() {
+                        finished = Some(Err("Evaluation thread stopped".to_string()));
+                    }
+                    break;
+                }
+            }
+        }
+        let result = finished?;
+        self.compiled_revision = self.pending.take().map(|x| x.1);
+        Some(result)
+    }
+}
+
+/// A copy of the patch that can be evaluated away from the editor. It shares the DAW automation,
+/// input and transport handles with `state`, but has its own sound queue and wavetables.
+pub fn detached_state(
+    state: &SoundNodeGraphState,
+    sample_rate: f32,
+    bpm: Arc<AtomicF32>,
+) -> SoundNodeGraph {
+    let mut job = SoundNodeGraph::default();
+    job.state.editor_state.graph = state.root_graph();
+    job.state.user_state.vst_output_node_id = state.user_state.vst_output_node_id;
+    job.state.user_state.automation_macros = state.user_state.automation_macros.clone();
+    job.state.user_state.tuning = state.user_state.tuning.clone();
+    // Every evaluation goes through here, so it's where the tuning table the synth reads from is
+    // kept up to date.
+    state
+        ._unserializeable_state
+        .tuning
+        .0
+        .store(&state.user_state.tuning);
+    let unserializeable = &mut job.state._unserializeable_state;
+    unserializeable.node_definitions = state._unserializeable_state.node_definitions.clone();
+    unserializeable.automations = state._unserializeable_state.automations.clone();
+    unserializeable.input = state._unserializeable_state.input.clone();
+    unserializeable.transport = state._unserializeable_state.transport.clone();
+    unserializeable.tuning = state._unserializeable_state.tuning.clone();
+    unserializeable.queue.set_sample_rate(sample_rate);
+    unserializeable.queue.set_bpm(bpm);
+    job
+}
+
+/// Evaluates the output node of `job` once per `(key, note speed)` pair. Blocks until done, so
+/// call it from a background thread.
+pub fn compile(mut job: SoundNodeGraph, notes: &[(usize, f32)]) -> CompileResult {
+    let output_id = match job.state.user_state.vst_output_node_id {
+        Some(x) => x,
+        None => return Err("The graph has no Output node".to_string()),
+    };
+    let graph = job.state.editor_state.graph.clone();
+    let definitions = job.state._unserializeable_state.node_definitions.clone();
+    let mut sources = vec![];
+    for (key, speed) in notes {
+        let queue = &mut job.state._unserializeable_state.queue;
+        queue.clear();
+        queue.set_note_speed(*speed);
+        let value = evaluate_node(
+            &graph,
+            output_id,
+            &mut HashMap::new(),
+            &definitions,
+            &mut job.state,
+        )
+        .map_err(|x| format!("{:?}", x))?;
+        let source_id = match value {
+            ValueType::AudioSource { value } => value,
+            _ => return Err("Output node did not produce audio".to_string()),
+        };
+        let sound = job
+            .state
+            ._unserializeable_state
+            .queue
+            .clone_sound(source_id)
+            .map_err(|x| format!("{:?}", x))?;
+        sources.push((*key, sound));
+    }
+    job.state._unserializeable_state.queue.clear();
+    Ok(sources)
+}
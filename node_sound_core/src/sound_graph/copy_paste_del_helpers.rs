@@ -18,6 +18,29 @@ pub struct ClipboardData {
     output_params: HashMap<OutputId, OutputParam<DataType>>,
 }
 
+impl ClipboardData {
+    /// Checks that every param the nodes refer to is present, so a bad clipboard can be reported
+    /// instead of panicking in `paste`.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.nodes.is_empty() {
+            return Err("Clipboard contains no nodes".to_string());
+        }
+        for (node, _) in self.nodes.iter() {
+            for (name, id) in node.inputs.iter() {
+                if !self.input_params.contains_key(id) {
+                    return Err(format!("Clipboard is missing input {}", name));
+                }
+            }
+            for (name, id) in node.outputs.iter() {
+                if !self.output_params.contains_key(id) {
+                    return Err(format!("Clipboard is missing output {}", name));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 pub fn delete_nodes(state: &mut SoundGraphEditorState, all: bool) {
     let nodes;
     if all {
@@ -170,5 +193,5 @@ pub async fn paste(
         }
     }
 
-    let _ = ids.iter().map(|x| state.selected_nodes.push(*x));
+    state.selected_nodes.extend(ids);
 }
@@ -2,12 +2,14 @@ use eframe::egui::{ComboBox, DragValue};
 use egui_extras_xt::knobs::AudioKnob;
 
 use super::note::{Note, Octave, Pitch};
+use super::tuning::Tuning;
 
 pub fn float_selector(
     value: &mut f32,
     min: &mut f32,
     max: &mut f32,
     note: &mut Pitch,
+    tuning: &Tuning,
     ui: &mut eframe::egui::Ui,
     param_name: &str,
 ) {
@@ -57,15 +59,15 @@ pub fn float_selector(
         match (note_res, octave_res) {
             (Ok(note_res), Err(_)) => {
                 *note = Pitch(note.0.clone(), note_res);
-                *value = note.match_freq();
+                *value = tuning.pitch_frequency(note).unwrap_or(*value);
             }
             (Err(_), Ok(octave_res)) => {
                 *note = Pitch(octave_res, note.1.clone());
-                *value = note.match_freq();
+                *value = tuning.pitch_frequency(note).unwrap_or(*value);
             }
             (Ok(note_res), Ok(octave_res)) => {
                 *note = Pitch(octave_res, note_res);
-                *value = note.match_freq();
+                *value = tuning.pitch_frequency(note).unwrap_or(*value);
             }
             (Err(_), Err(_)) => {}
         }
@@ -1,23 +1,36 @@
-use super::copy_paste_del_helpers::{copy, delete_nodes, paste};
+use super::arpeggiator::ArpSettings;
+use super::audition;
+use super::automation::{self, AutomationMacros};
+use super::clipboard::{self, Clipboard};
+use super::compiler::Compiler;
 use super::float_selector;
 use super::graph_types::InputValueConfig;
+use super::groups::{self, GroupData, GroupFrame, GroupLibrary};
+use super::history::{self, History};
+use super::midi_export;
+use super::node_finder::{self, NodeFinder};
+use super::patch_files::{self, PatchMetadata, PresetBrowser};
+use super::scope::{self, Scopes};
+use super::tracker_grid;
+use super::tuning::{self, Tuning, TuningFile, TuningTable};
+use super::voice_mode::VoiceSettings;
 use super::wave_table_graph::wave_table_graph;
-use crate::nodes::{NodeDefinitions, SoundNode, SoundNodeProps};
-use crate::sound_graph::copy_paste_del_helpers::ClipboardData;
+use crate::nodes::{NodeCategory, NodeDefinitions, SoundNode, SoundNodeProps, step_sequencer_node};
 use crate::sound_graph::graph_types::{DataType, ValueType};
-use crate::sound_graph::note::{Note, NoteSpeed};
 use crate::sound_graph::themes::AppTheme;
-use crate::sound_map::SoundQueue;
-use crate::sounds::tracker::TrackerNote;
+use crate::sound_map::{AtomicF32, SoundQueue};
+use crate::sounds::input::InputHistory;
+use crate::sounds::midi::read_tempo_changes;
+use crate::sounds::transport::HostTransport;
 use crate::sounds::wave_table::WaveTableManager;
 use eframe::egui::{self, ComboBox, DragValue, Vec2, Widget};
 use eframe::egui::{Checkbox, Pos2, WidgetText};
 pub use egui_node_graph_2::*;
-use futures::executor;
 pub use rodio::source::Zero;
 use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
 use std::fs;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::{borrow::Cow, collections::HashMap, time::Duration};
 use synthrs::midi;
@@ -25,6 +38,8 @@ use synthrs::midi;
 #[derive(Clone, Serialize, Deserialize, Debug, Default)]
 pub struct NodeData {
     pub name: String,
+    #[serde(default)]
+    pub group: Option<GroupData>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
@@ -55,6 +70,30 @@ pub struct SoundGraphUserState {
     pub wave_shaper_graph_id: usize,
     #[serde(default)]
     pub current_theme: AppTheme,
+    #[serde(default)]
+    pub patch_metadata: PatchMetadata,
+    #[serde(default)]
+    pub favourite_nodes: Vec<String>,
+    #[serde(default)]
+    pub recent_nodes: Vec<String>,
+    #[serde(default)]
+    pub automation_macros: AutomationMacros,
+    #[serde(default)]
+    pub arpeggiator: ArpSettings,
+    #[serde(default)]
+    pub tuning: Tuning,
+    #[serde(default)]
+    pub voices: VoiceSettings,
+    /// Kept out of the saved state, but lives as long as the plugin instance so it survives the
+    /// editor window being closed and reopened.
+    #[serde(skip)]
+    pub history: History,
+    #[serde(skip)]
+    pub scopes: Scopes,
+    #[serde(skip)]
+    pub open_group: Option<NodeId>,
+    #[serde(skip)]
+    pub save_group: Option<NodeId>,
     #[serde(skip)]
     pub files: Arc<Mutex<FileManager>>,
     #[serde(skip)]
@@ -94,14 +133,14 @@ impl DataTypeTrait<SoundGraphUserState> for DataType {
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct NodeDefinitionUi(pub SoundNode);
+pub struct NodeDefinitionUi(pub SoundNode, pub Option<GroupData>);
 
 impl NodeTemplateTrait for NodeDefinitionUi {
     type NodeData = NodeData;
     type DataType = DataType;
     type ValueType = ValueType;
     type UserState = SoundGraphUserState;
-    type CategoryType = ();
+    type CategoryType = NodeCategory;
 
     fn tooltip(&self) -> Option<String> {
         Some(self.0.tooltip.clone())
@@ -115,9 +154,14 @@ impl NodeTemplateTrait for NodeDefinitionUi {
         self.node_finder_label(user_state).into()
     }
 
+    fn node_finder_categories(&self, _user_state: &mut Self::UserState) -> Vec<NodeCategory> {
+        vec![self.0.category]
+    }
+
     fn user_data(&self, _user_state: &mut Self::UserState) -> Self::NodeData {
         NodeData {
             name: self.0.name.clone(),
+            group: self.1.clone(),
         }
     }
 
@@ -128,6 +172,10 @@ impl NodeTemplateTrait for NodeDefinitionUi {
         node_id: NodeId,
     ) {
         user_state.is_saved = false;
+        if let Some(group) = &self.1 {
+            groups::add_group_params(graph, node_id, group);
+            return;
+        }
         for input in self.0.inputs.iter() {
             graph.add_input_param(
                 node_id,
@@ -148,7 +196,10 @@ impl NodeTemplateTrait for NodeDefinitionUi {
                         value: Duration::from_secs_f32(*value),
                     },
                     InputValueConfig::AudioFile {} => ValueType::AudioFile { value: None },
-                    InputValueConfig::MidiFile {} => ValueType::MidiFile { value: None },
+                    InputValueConfig::MidiFile {} => ValueType::MidiFile {
+                        value: None,
+                        tempo_changes: vec![],
+                    },
                     InputValueConfig::Graph {
                         value,
                         width,
@@ -169,6 +220,9 @@ impl NodeTemplateTrait for NodeDefinitionUi {
                         value: value.clone(),
                         values: values.clone(),
                     },
+                    InputValueConfig::Macro { value } => ValueType::Macro {
+                        value: value.clone(),
+                    },
                 },
                 input.1.kind,
                 true,
@@ -180,7 +234,7 @@ impl NodeTemplateTrait for NodeDefinitionUi {
     }
 }
 
-pub struct NodeDefinitionsUi<'a>(&'a NodeDefinitions);
+pub struct NodeDefinitionsUi<'a>(pub &'a NodeDefinitions, pub &'a GroupLibrary);
 impl<'a> NodeTemplateIter for NodeDefinitionsUi<'a> {
     type Item = NodeDefinitionUi;
 
@@ -189,8 +243,19 @@ impl<'a> NodeTemplateIter for NodeDefinitionsUi<'a> {
             .0
             .values()
             .cloned()
-            .map(|x| x.0)
-            .map(NodeDefinitionUi)
+            .map(|x| NodeDefinitionUi(x.0, None))
+            .chain(self.1.groups.iter().map(|group| {
+                NodeDefinitionUi(
+                    SoundNode {
+                        name: group.name.clone(),
+                        category: NodeCategory::Groups,
+                        tooltip: "A saved group from your library.".to_string(),
+                        inputs: Default::default(),
+                        outputs: Default::default(),
+                    },
+                    Some(group.clone()),
+                )
+            }))
             .collect()
     }
 }
@@ -209,51 +274,12 @@ impl WidgetValueTrait for ValueType {
     ) -> Vec<ActiveNodeState> {
         match self {
             ValueType::TrackerNotes { notes } => {
-                ui.horizontal(|ui| {
-                    for (index, tracker_note) in notes.iter_mut().enumerate() {
-                        ui.vertical(|ui| {
-                            egui::ComboBox::from_id_salt(format!("speed_{}", index))
-                                .selected_text(tracker_note.speed.to_string())
-                                .width(100.0)
-                                .show_ui(ui, |ui| {
-                                    for speed_variant in NoteSpeed::ALL {
-                                        ui.selectable_value(
-                                            &mut tracker_note.speed,
-                                            speed_variant.clone(),
-                                            speed_variant.to_string(),
-                                        );
-                                    }
-                                });
-
-                            egui::ComboBox::from_id_salt(format!("note_{}", index))
-                                .selected_text(match &tracker_note.note {
-                                    Some(note) => note.to_string(),
-                                    None => "~".to_string(),
-                                })
-                                .width(20.0)
-                                .show_ui(ui, |ui| {
-                                    ui.selectable_value(&mut tracker_note.note, None, "~");
-
-                                    for note_variant in Note::ALL {
-                                        ui.selectable_value(
-                                            &mut tracker_note.note,
-                                            Some(note_variant.clone()),
-                                            note_variant.to_string(),
-                                        );
-                                    }
-                                });
-                        });
+                tracker_grid::tracker_grid(ui, node_id, notes);
+                if ui.button("Export MIDI…").clicked() {
+                    if let Ok(mut files) = user_state.files.lock() {
+                        files.midi_export_active = Some(node_id);
                     }
-                    if notes.len() > 1 {
-                        if ui.button("−").clicked() {
-                            notes.remove(notes.len() - 1);
-                            return;
-                        }
-                    }
-                    if ui.button("+").clicked() {
-                        notes.push(TrackerNote::default());
-                    }
-                });
+                }
             }
             ValueType::Dropdown { value, values } => {
                 ui.horizontal(|ui| {
@@ -280,6 +306,19 @@ impl WidgetValueTrait for ValueType {
                     }
                 });
             }
+            ValueType::Macro { value } => {
+                ui.horizontal(|ui| {
+                    ui.label(param_name);
+                    ComboBox::from_id_salt(format!("macro_{:?}_{}", node_id, param_name))
+                        .selected_text(value.clone())
+                        .width(100.0)
+                        .show_ui(ui, |ui| {
+                            for name in user_state.automation_macros.names() {
+                                ui.selectable_value(value, name.clone(), name);
+                            }
+                        });
+                });
+            }
             ValueType::Bool { value } => {
                 Checkbox::new(value, WidgetText::from(param_name)).ui(ui);
             }
@@ -294,7 +333,15 @@ impl WidgetValueTrait for ValueType {
                 min,
                 max,
                 note,
-            } => float_selector::float_selector(value, min, max, note, ui, param_name),
+            } => float_selector::float_selector(
+                value,
+                min,
+                max,
+                note,
+                &user_state.tuning,
+                ui,
+                param_name,
+            ),
             ValueType::Duration { value } => {
                 ui.horizontal(|ui| {
                     ui.label(param_name);
@@ -340,7 +387,10 @@ impl WidgetValueTrait for ValueType {
                     Err(_) => {}
                 }
             }
-            ValueType::MidiFile { value } => {
+            ValueType::MidiFile {
+                value,
+                tempo_changes,
+            } => {
                 let y = &value.clone();
                 let file_name = match y {
                     Some(x) => std::path::Path::new(&x.0)
@@ -360,7 +410,13 @@ impl WidgetValueTrait for ValueType {
                                 if node_id == x.1 {
                                     match midi::read_midi_file(x.0.clone()) {
                                         Err(_x) => {}
-                                        Ok(x2) => *value = Some((x.0.clone(), x2)),
+                                        Ok(x2) => {
+                                            *value = Some((x.0.clone(), x2));
+                                            *tempo_changes = match read_tempo_changes(&x.0) {
+                                                Ok(x) => x,
+                                                Err(_x) => vec![],
+                                            };
+                                        }
                                     };
                                 }
                             }
@@ -393,25 +449,71 @@ impl NodeDataTrait for NodeData {
         ActiveNodeState: UserResponseTrait,
     {
         let mut responses = vec![];
+        if self.group.is_some() {
+            ui.horizontal(|ui| {
+                if ui.button("Open group").clicked() {
+                    user_state.open_group = Some(node_id);
+                }
+                if ui.button("Save to library").clicked() {
+                    user_state.save_group = Some(node_id);
+                }
+            });
+        }
+        if self.group.is_none() && self.name == step_sequencer_node::NAME {
+            if ui
+                .button("Export MIDI…")
+                .on_hover_text("Writes one loop of the gates as notes on middle C")
+                .clicked()
+            {
+                if let Ok(mut files) = user_state.files.lock() {
+                    files.midi_export_active = Some(node_id);
+                }
+            }
+        }
         let is_playing: bool = match user_state.active_node {
             ActiveNodeState::PlayingNode(x) => x == node_id,
             _ => false,
         };
-        if !is_playing {
-            if ui.button("▶ Play").clicked() {
-                if user_state.active_node == ActiveNodeState::NoNode {
-                    responses.push(NodeResponse::User(ActiveNodeState::PlayingNode(node_id)));
+        ui.horizontal(|ui| {
+            if !is_playing {
+                if ui.button("▶ Play").clicked() {
+                    if user_state.active_node == ActiveNodeState::NoNode {
+                        responses.push(NodeResponse::User(ActiveNodeState::PlayingNode(node_id)));
+                        user_state.active_modified = true;
+                    }
+                }
+            } else {
+                let button =
+                    egui::Button::new(egui::RichText::new("⏹ Stop").color(egui::Color32::BLACK))
+                        .fill(egui::Color32::GOLD);
+                if ui.add(button).clicked() {
+                    responses.push(NodeResponse::User(ActiveNodeState::NoNode));
                     user_state.active_modified = true;
                 }
             }
-        } else {
-            let button =
-                egui::Button::new(egui::RichText::new("⏹ Stop").color(egui::Color32::BLACK))
-                    .fill(egui::Color32::GOLD);
-            if ui.add(button).clicked() {
-                responses.push(NodeResponse::User(ActiveNodeState::NoNode));
-                user_state.active_modified = true;
+            let is_recording = user_state.active_node == ActiveNodeState::RecordingNode(node_id);
+            if is_recording {
+                ui.add_enabled(false, egui::Button::new("⏺ Recording…"));
+            } else if ui.button("⏺ Record").clicked() {
+                if user_state.active_node == ActiveNodeState::NoNode {
+                    if let Ok(mut files) = user_state.files.lock() {
+                        files.record_active = Some(node_id);
+                    }
+                    responses.push(NodeResponse::User(ActiveNodeState::RecordingNode(node_id)));
+                }
+            }
+            let mut seconds = user_state.recording_length.max(1);
+            ui.add(DragValue::new(&mut seconds).range(1..=600).suffix(" s"))
+                .on_hover_text("Recording length");
+            user_state.recording_length = seconds;
+            let scope_open = user_state.scopes.previews.contains_key(&node_id);
+            if ui.selectable_label(scope_open, "〰 Scope").clicked() {
+                user_state.scopes.toggle(node_id);
             }
+        });
+        let live = user_state.scopes.is_live() && user_state.vst_output_node_id == Some(node_id);
+        if let Some(preview) = user_state.scopes.previews.get_mut(&node_id) {
+            scope::scope_ui(ui, node_id, preview, live);
         }
 
         responses
@@ -427,6 +529,8 @@ pub type SoundGraphEditorState =
 pub struct SoundNodeGraphState {
     pub user_state: SoundGraphUserState,
     pub editor_state: SoundGraphEditorState,
+    #[serde(default)]
+    pub group_stack: Vec<GroupFrame>,
     #[serde(skip)]
     pub _unserializeable_state: UnserializeableGraphState,
 }
@@ -437,6 +541,18 @@ pub struct FileManager {
     pub wav_active: Option<NodeId>,
     pub midi_file_path: Option<(String, NodeId)>,
     pub wav_file_path: Option<(String, NodeId)>,
+    pub patch_save_active: bool,
+    pub patch_open_active: bool,
+    pub patch_save_path: Option<PathBuf>,
+    pub patch_open_path: Option<PathBuf>,
+    pub record_active: Option<NodeId>,
+    pub record_path: Option<(PathBuf, NodeId)>,
+    /// Left by the recording thread once the file is written.
+    pub record_result: Option<Result<(), String>>,
+    pub midi_export_active: Option<NodeId>,
+    pub midi_export_path: Option<(PathBuf, NodeId)>,
+    pub tuning_active: Option<TuningFile>,
+    pub tuning_path: Option<(PathBuf, TuningFile)>,
 }
 
 #[derive(Default)]
@@ -446,13 +562,28 @@ pub struct UnserializeableGraphState {
     pub queue: SoundQueue,
     pub automations: DAWAutomations,
     pub input: DAWInput,
+    pub transport: DAWTransport,
+    pub tuning: DAWTuning,
+    pub preset_browser: PresetBrowser,
+    pub group_library: GroupLibrary,
+    pub node_finder: NodeFinder,
+    pub clipboard: Clipboard,
+    pub compiler: Compiler,
+    pub error: Option<String>,
 }
 
 #[derive(Default, Clone)]
-pub struct DAWAutomations(pub [Arc<Mutex<f32>>; 18]);
+pub struct DAWAutomations(pub [Arc<AtomicF32>; automation::AUTOMATION_COUNT]);
 
+/// The recent input of each of the plugin's input buses.
 #[derive(Default, Clone)]
-pub struct DAWInput(pub Arc<Mutex<(f32, f32)>>);
+pub struct DAWInput(pub Arc<InputHistory>);
+
+#[derive(Default, Clone)]
+pub struct DAWTransport(pub Arc<HostTransport>);
+
+#[derive(Default, Clone)]
+pub struct DAWTuning(pub Arc<TuningTable>);
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct SoundNodeGraph {
@@ -481,6 +612,9 @@ impl SoundNodeGraph {
     }
 
     fn update_output_node(&mut self) {
+        if !self.state.group_stack.is_empty() {
+            return;
+        }
         let mut found = false;
         for node in self.state.editor_state.graph.iter_nodes() {
             let found_match = match self.state.editor_state.graph.nodes.get(node) {
@@ -497,9 +631,37 @@ impl SoundNodeGraph {
         }
     }
 
+    fn update_groups(&mut self) {
+        self.state._unserializeable_state.group_library.load_once();
+        if let Some(node_id) = self.state.user_state.open_group.take() {
+            groups::open_group(&mut self.state, node_id);
+        }
+        if let Some(node_id) = self.state.user_state.save_group.take() {
+            let group = self
+                .state
+                .editor_state
+                .graph
+                .nodes
+                .get(node_id)
+                .and_then(|x| x.user_data.group.clone());
+            if let Some(group) = group {
+                match groups::save_group_to_library(&group) {
+                    Ok(_) => self.state._unserializeable_state.group_library.refresh(),
+                    Err(err) => {
+                        self.state._unserializeable_state.error =
+                            Some(format!("Saving group failed: {}", err))
+                    }
+                }
+            }
+        }
+    }
+
     pub fn update_root(&mut self, ctx: &egui::Context) {
         self.update_output_node();
+        self.update_groups();
         self.state.user_state.current_theme.apply_theme(ctx);
+        history::handle_shortcuts(ctx, &mut self.state);
+        clipboard::handle_shortcuts(ctx, &mut self.state);
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("Theme", |ui| {
@@ -516,16 +678,59 @@ impl SoundNodeGraph {
                         }
                     }
                 });
+                ui.menu_button("Edit", |ui| {
+                    let undo = egui::Button::new("Undo")
+                        .shortcut_text(ctx.format_shortcut(&history::UNDO_SHORTCUT));
+                    if ui
+                        .add_enabled(self.state.user_state.history.can_undo(), undo)
+                        .clicked()
+                    {
+                        self.state.undo();
+                        ui.close_menu();
+                    }
+                    let redo = egui::Button::new("Redo")
+                        .shortcut_text(ctx.format_shortcut(&history::REDO_SHORTCUT));
+                    if ui
+                        .add_enabled(self.state.user_state.history.can_redo(), redo)
+                        .clicked()
+                    {
+                        self.state.redo();
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("Patch", |ui| {
+                    if ui.button("Save patch…").clicked() {
+                        if let Ok(mut files) = self.state.user_state.files.lock() {
+                            files.patch_save_active = true;
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Open patch…").clicked() {
+                        if let Ok(mut files) = self.state.user_state.files.lock() {
+                            files.patch_open_active = true;
+                        }
+                        ui.close_menu();
+                    }
+                    let browser = &mut self.state._unserializeable_state.preset_browser;
+                    if ui.checkbox(&mut browser.open, "Preset browser").changed() {
+                        browser.refresh();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .checkbox(&mut self.state.user_state.tuning.open, "Tuning")
+                        .changed()
+                    {
+                        ui.close_menu();
+                    }
+                });
                 ui.add(egui::Label::new(env!("CARGO_PKG_VERSION")));
                 ui.add(egui::Label::new("|"));
+                ui.toggle_value(
+                    &mut self.state._unserializeable_state.node_finder.open,
+                    "🔍 nodes",
+                );
                 if ui.add(egui::Button::new("copy")).clicked() {
-                    let data = copy(&mut self.state.editor_state, false);
-                    let mut clipboard =
-                        arboard::Clipboard::new().expect("clipboard creation failed");
-                    clipboard
-                        .set()
-                        .text(ron::ser::to_string(&data).expect("expect serialize to work..."))
-                        .expect("clipboard write failed");
+                    clipboard::copy_selected(&mut self.state);
                 }
                 if ui.add(egui::Button::new("paste")).clicked() {
                     let input =
@@ -534,24 +739,51 @@ impl SoundNodeGraph {
                         x: input.x,
                         y: input.y,
                     } + Vec2 { x: 0.0, y: 1000.0 };
-                    let mut clipboard =
-                        arboard::Clipboard::new().expect("clipboard creation failed");
-                    let data: ClipboardData =
-                        ron::de::from_str(&clipboard.get().text().expect("clipboard read failed"))
-                            .expect("expect deserialize to work...");
-                    executor::block_on(paste(&mut self.state.editor_state, Some(input_vec2), data));
+                    clipboard::paste_clipboard(&mut self.state, input_vec2, None);
+                }
+                if ui.add(egui::Button::new("duplicate")).clicked() {
+                    clipboard::duplicate_selected(&mut self.state);
                 }
                 if ui.add(egui::Button::new("delete selected")).clicked() {
-                    delete_nodes(&mut self.state.editor_state, false);
+                    clipboard::delete_selected(&mut self.state);
+                }
+                if ui.add(egui::Button::new("group selected")).clicked() {
+                    if let Err(err) = groups::collapse_selection(&mut self.state) {
+                        self.state._unserializeable_state.error = Some(err);
+                    }
+                }
+                if let Some(frame) = self.state.group_stack.last_mut() {
+                    ui.add(egui::Label::new("|"));
+                    ui.label("editing group:");
+                    ui.add(egui::TextEdit::singleline(&mut frame.name).desired_width(100.0));
+                    if ui.add(egui::Button::new("⬑ close group")).clicked() {
+                        groups::close_group(&mut self.state);
+                    }
+                }
+                if let Some(error) = &self.state._unserializeable_state.error {
+                    ui.add(egui::Label::new("|"));
+                    ui.colored_label(ui.visuals().error_fg_color, error);
                 }
             });
         });
 
+        patch_files::handle_patch_files(&mut self.state);
+        audition::handle_recording(&mut self.state);
+        midi_export::handle_midi_export(&mut self.state);
+        tuning::handle_tuning_files(&mut self.state);
+        patch_files::preset_browser(ctx, &mut self.state);
+        node_finder::node_finder(ctx, &mut self.state);
+        automation::macros_window(ctx, &mut self.state);
+        tuning::tuning_window(ctx, &mut self.state);
+
         let graph_response = egui::CentralPanel::default()
             .show(ctx, |ui| {
                 self.state.editor_state.draw_graph_editor(
                     ui,
-                    NodeDefinitionsUi(&self.state._unserializeable_state.node_definitions),
+                    NodeDefinitionsUi(
+                        &self.state._unserializeable_state.node_definitions,
+                        &self.state._unserializeable_state.group_library,
+                    ),
                     &mut self.state.user_state,
                     Vec::default(),
                 )
@@ -559,16 +791,38 @@ impl SoundNodeGraph {
             .inner;
 
         for node_response in graph_response.node_responses {
-            if let NodeResponse::User(user_event) = node_response {
-                self.state.user_state.active_node = user_event;
+            match node_response {
+                NodeResponse::User(user_event) => {
+                    self.state.user_state.active_node = user_event;
+                }
+                NodeResponse::CreatedNode(node_id) => {
+                    if let Some(node) = self.state.editor_state.graph.nodes.get(node_id) {
+                        let name = node.user_data.name.clone();
+                        node_finder::remember_recent(&mut self.state.user_state, &name);
+                    }
+                    self.state.user_state.history.mark_changed();
+                }
+                NodeResponse::SelectNode(_)
+                | NodeResponse::RaiseNode(_)
+                | NodeResponse::ConnectEventStarted(..) => {}
+                _ => self.state.user_state.history.mark_changed(),
             }
         }
+
+        self.state.record_history(ctx);
+        scope::update_previews(ctx, &mut self.state);
     }
 }
 
 impl eframe::App for SoundNodeGraph {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.update_root(ctx)
+        self.update_root(ctx);
+        if let Ok(mut files) = self.state.user_state.files.lock() {
+            files.open_patch_dialogs();
+            files.open_record_dialog();
+            files.open_midi_export_dialog();
+            files.open_tuning_dialog();
+        }
     }
 }
 
@@ -581,19 +835,19 @@ pub fn evaluate_node<'a>(
     all_nodes: &NodeDefinitions,
     state: &'a mut SoundNodeGraphState,
 ) -> Result<ValueType, Box<dyn std::error::Error>> {
-    let node = match all_nodes.0.get(
-        &match graph.nodes.get(node_id) {
-            Some(x) => x,
-            None => {
-                return Err(Box::new(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "Node Deref Failed: Failed to get Node Data",
-                )));
-            }
+    let node_data = match graph.nodes.get(node_id) {
+        Some(x) => x,
+        None => {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Node Deref Failed: Failed to get Node Data",
+            )));
         }
-        .user_data
-        .name,
-    ) {
+    };
+    if let Some(group) = &node_data.user_data.group {
+        return evaluate_group(graph, node_id, group, outputs_cache, all_nodes, state);
+    }
+    let node = match all_nodes.0.get(&node_data.user_data.name) {
         Some(x) => x,
         None => {
             return Err(Box::new(std::io::Error::new(
@@ -658,6 +912,58 @@ pub fn evaluate_node<'a>(
     }
 }
 
+/// Evaluates a group node by evaluating its contents with the group's inputs substituted in for
+/// the params they were connected to.
+fn evaluate_group<'a>(
+    graph: &MyGraph,
+    node_id: NodeId,
+    group: &GroupData,
+    outputs_cache: &mut OutputsCache,
+    all_nodes: &NodeDefinitions,
+    state: &'a mut SoundNodeGraphState,
+) -> Result<ValueType, Box<dyn std::error::Error>> {
+    let mut inner = group.graph.clone();
+    for (name, inner_input) in group.inputs.iter() {
+        let value = evaluate_input(graph, node_id, name, outputs_cache, all_nodes, state)?;
+        if let Some(param) = inner.inputs.get_mut(*inner_input) {
+            param.value = value;
+        }
+    }
+
+    let mut inner_cache = OutputsCache::new();
+    let mut result = None;
+    for (name, inner_output) in group.outputs.iter() {
+        if !inner_cache.contains_key(inner_output) {
+            let inner_node = match inner.outputs.get(*inner_output) {
+                Some(x) => x.node,
+                None => continue,
+            };
+            evaluate_node(&inner, inner_node, &mut inner_cache, all_nodes, state)?;
+        }
+        let value = match inner_cache.get(inner_output) {
+            Some(x) => x.clone(),
+            None => {
+                return Err(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "Group output was not evaluated",
+                )));
+            }
+        };
+        populate_output(graph, outputs_cache, node_id, name, value.clone())?;
+        if result.is_none() {
+            result = Some(value);
+        }
+    }
+
+    match result {
+        Some(x) => Ok(x),
+        None => Err(Box::new(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Group had no output",
+        ))),
+    }
+}
+
 fn populate_output<'a>(
     graph: &'a MyGraph,
     outputs_cache: &'a mut OutputsCache,
@@ -42,6 +42,9 @@ pub enum ValueType {
     },
     MidiFile {
         value: Option<(String, MidiSong)>,
+        /// The file's tempo changes as `(tick, bpm)`, which the parsed song doesn't keep.
+        #[serde(default)]
+        tempo_changes: Vec<(u64, f64)>,
     },
     Graph {
         value: Option<Vec<f32>>,
@@ -59,6 +62,10 @@ pub enum ValueType {
     TrackerNotes {
         notes: Vec<TrackerNote>,
     },
+    /// The name of an automation macro.
+    Macro {
+        value: String,
+    },
 }
 
 impl Default for &ValueType {
@@ -95,6 +102,9 @@ pub enum InputValueConfig {
     TrackerNotes {
         notes: Vec<TrackerNote>,
     },
+    Macro {
+        value: String,
+    },
 }
 
 impl Debug for ValueType {
@@ -115,7 +125,7 @@ impl Debug for ValueType {
             Self::AudioFile { value } => f
                 .debug_struct(&value.clone().unwrap_or(("None".to_string(), vec![])).0)
                 .finish(),
-            Self::MidiFile { value: _ } => f
+            Self::MidiFile { .. } => f
                 .debug_struct("Midi")
                 .field("value", &"Anonymous MidiFile")
                 .finish(),
@@ -146,6 +156,7 @@ impl Debug for ValueType {
                 .debug_struct("Dropdown")
                 .field("value", &"Anonymous Dropdown")
                 .finish(),
+            Self::Macro { value } => f.debug_struct("Macro").field("value", value).finish(),
         }
     }
 }
@@ -192,9 +203,12 @@ impl ValueType {
             _ => Err("invalid cast".to_string()),
         }
     }
-    pub fn try_to_midi(self) -> Result<Option<(String, MidiSong)>, String> {
+    pub fn try_to_midi(self) -> Result<Option<(MidiSong, Vec<(u64, f64)>)>, String> {
         match self {
-            ValueType::MidiFile { value } => Ok(value),
+            ValueType::MidiFile {
+                value,
+                tempo_changes,
+            } => Ok(value.map(|x| (x.1, tempo_changes))),
             _ => Err("invalid cast".to_string()),
         }
     }
@@ -217,6 +231,13 @@ impl ValueType {
         }
     }
 
+    pub fn try_to_macro(self) -> Result<String, String> {
+        match self {
+            ValueType::Macro { value } => Ok(value),
+            _ => Err("invalid cast".to_string()),
+        }
+    }
+
     pub fn try_to_tracker(self) -> Result<Vec<TrackerNote>, String> {
         match self {
             ValueType::TrackerNotes { notes } => Ok(notes),
@@ -0,0 +1,372 @@
+use super::graph::{NodeData, SoundGraphEditorState, SoundNodeGraphState};
+use super::graph_types::{DataType, ValueType};
+use eframe::egui::{Pos2, Vec2};
+use egui_node_graph_2::{Graph, InputId, NodeId, OutputId};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const GROUP_EXTENSION: &str = "nsg";
+
+/// A collapsed selection of nodes. `inputs` and `outputs` map the group node's exposed params to
+/// the params inside `graph` they stand in for.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct GroupData {
+    pub name: String,
+    pub graph: Graph<NodeData, DataType, ValueType>,
+    pub positions: Vec<(NodeId, Pos2)>,
+    pub inputs: Vec<(String, InputId)>,
+    pub outputs: Vec<(String, OutputId)>,
+}
+
+/// The parent graph to return to when the group being edited is closed.
+#[derive(Serialize, Deserialize)]
+pub struct GroupFrame {
+    pub node_id: NodeId,
+    pub name: String,
+    pub parent: SoundGraphEditorState,
+}
+
+#[derive(Default)]
+pub struct GroupLibrary {
+    pub groups: Vec<GroupData>,
+    loaded: bool,
+}
+
+impl GroupLibrary {
+    pub fn load_once(&mut self) {
+        if !self.loaded {
+            self.refresh();
+        }
+    }
+
+    pub fn refresh(&mut self) {
+        self.loaded = true;
+        self.groups = match groups_folder() {
+            Some(folder) => scan_groups(&folder),
+            None => vec![],
+        };
+    }
+}
+
+/// The folder saved groups are written to and offered in the node finder from.
+pub fn groups_folder() -> Option<PathBuf> {
+    dirs::data_dir().map(|x| x.join("node_sound").join("groups"))
+}
+
+pub fn scan_groups(folder: &Path) -> Vec<GroupData> {
+    let dir = match fs::read_dir(folder) {
+        Ok(x) => x,
+        Err(_x) => return vec![],
+    };
+    let mut groups = dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .map(|x| x == GROUP_EXTENSION)
+                .unwrap_or(false)
+        })
+        .filter_map(|path| ron::de::from_str(&fs::read_to_string(path).ok()?).ok())
+        .collect::<Vec<GroupData>>();
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    groups
+}
+
+pub fn save_group_to_library(group: &GroupData) -> Result<(), Box<dyn std::error::Error>> {
+    let folder = match groups_folder() {
+        Some(x) => x,
+        None => return Err("No group library folder available".into()),
+    };
+    fs::create_dir_all(&folder)?;
+    let file_name: String = group
+        .name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    fs::write(
+        folder.join(format!("{}.{}", file_name, GROUP_EXTENSION)),
+        ron::ser::to_string_pretty(group, ron::ser::PrettyConfig::default())?,
+    )?;
+    Ok(())
+}
+
+fn unique_name(names: &[(String, impl Copy)], name: &str) -> String {
+    if !names.iter().any(|(x, _)| x == name) {
+        return name.to_string();
+    }
+    let mut n = 2;
+    while names.iter().any(|(x, _)| *x == format!("{} {}", name, n)) {
+        n += 1;
+    }
+    format!("{} {}", name, n)
+}
+
+/// Adds a group node's exposed params to `node_id`, copying type, default value and kind from
+/// the params they stand in for. Params the node already has are left alone.
+pub fn add_group_params(
+    graph: &mut Graph<NodeData, DataType, ValueType>,
+    node_id: NodeId,
+    group: &GroupData,
+) {
+    for (name, inner_input) in group.inputs.iter() {
+        if graph[node_id].get_input(name).is_ok() {
+            continue;
+        }
+        if let Some(param) = group.graph.inputs.get(*inner_input) {
+            graph.add_input_param(
+                node_id,
+                name.clone(),
+                param.typ,
+                param.value.clone(),
+                param.kind,
+                true,
+            );
+        }
+    }
+    for (name, inner_output) in group.outputs.iter() {
+        if graph[node_id].get_output(name).is_ok() {
+            continue;
+        }
+        if let Some(param) = group.graph.outputs.get(*inner_output) {
+            graph.add_output_param(node_id, name.clone(), param.typ);
+        }
+    }
+}
+
+/// Brings the params of the group node `node_id` in line with `group`, removing the ones it no
+/// longer exposes along with their connections, and adding any that are missing.
+fn sync_group_params(
+    graph: &mut Graph<NodeData, DataType, ValueType>,
+    node_id: NodeId,
+    group: &GroupData,
+) {
+    let stale_inputs = graph[node_id]
+        .inputs
+        .iter()
+        .filter(|(name, _)| !group.inputs.iter().any(|(x, _)| x == name))
+        .map(|(_, x)| *x)
+        .collect::<Vec<_>>();
+    for input_id in stale_inputs {
+        graph.remove_input_param(input_id);
+    }
+    let stale_outputs = graph[node_id]
+        .outputs
+        .iter()
+        .filter(|(name, _)| !group.outputs.iter().any(|(x, _)| x == name))
+        .map(|(_, x)| *x)
+        .collect::<Vec<_>>();
+    for output_id in stale_outputs {
+        graph.remove_output_param(output_id);
+    }
+    add_group_params(graph, node_id, group);
+}
+
+/// Adds a node for `group` to the editor at `position`, returning its id.
+pub fn add_group_node(
+    editor: &mut SoundGraphEditorState,
+    group: GroupData,
+    position: Pos2,
+) -> NodeId {
+    let user_data = NodeData {
+        name: group.name.clone(),
+        group: Some(group.clone()),
+    };
+    let node_id = editor.graph.add_node(
+        group.name.clone(),
+        Some("A group of nodes. Open it to edit its contents.".to_string()),
+        user_data,
+        |graph, node_id| add_group_params(graph, node_id, &group),
+    );
+    editor.node_order.push(node_id);
+    editor.node_positions.insert(node_id, position);
+    node_id
+}
+
+/// Replaces the selected nodes with a single group node. Connections crossing the selection
+/// boundary become the group's exposed inputs and outputs.
+pub fn collapse_selection(state: &mut SoundNodeGraphState) -> Result<NodeId, String> {
+    let editor = &mut state.editor_state;
+    let selected = editor
+        .selected_nodes
+        .iter()
+        .filter(|x| editor.graph.nodes.contains_key(**x))
+        .cloned()
+        .collect::<Vec<_>>();
+    if selected.is_empty() {
+        return Err("Select the nodes to group first".to_string());
+    }
+
+    let mut inner = editor.graph.clone();
+    let outside = inner
+        .iter_nodes()
+        .filter(|x| !selected.contains(x))
+        .collect::<Vec<_>>();
+    for node_id in outside {
+        inner.remove_node(node_id);
+    }
+
+    let mut inputs: Vec<(String, InputId)> = vec![];
+    let mut input_sources: Vec<OutputId> = vec![];
+    let mut outputs: Vec<(String, OutputId)> = vec![];
+    let mut output_targets: Vec<Vec<InputId>> = vec![];
+    for node_id in selected.iter() {
+        for (param_name, input_id) in editor.graph[*node_id].inputs.iter() {
+            if let Some(source) = editor.graph.connection(*input_id) {
+                if !selected.contains(&editor.graph[source].node) {
+                    inputs.push((unique_name(&inputs, param_name), *input_id));
+                    input_sources.push(source);
+                }
+            }
+        }
+    }
+    for (input_id, output_id) in editor.graph.connections.iter() {
+        if !selected.contains(&editor.graph[*output_id].node)
+            || selected.contains(&editor.graph[input_id].node)
+        {
+            continue;
+        }
+        match outputs.iter().position(|(_, x)| x == output_id) {
+            Some(idx) => output_targets[idx].push(input_id),
+            None => {
+                outputs.push((unique_name(&outputs, "out"), *output_id));
+                output_targets.push(vec![input_id]);
+            }
+        }
+    }
+
+    let positions = selected
+        .iter()
+        .filter_map(|x| editor.node_positions.get(*x).map(|pos| (*x, *pos)))
+        .collect::<Vec<_>>();
+    let center = positions
+        .iter()
+        .fold(Vec2::ZERO, |acc, (_, pos)| acc + pos.to_vec2())
+        / positions.len().max(1) as f32;
+
+    for node_id in selected.iter() {
+        editor.graph.remove_node(*node_id);
+        if let Some(x) = editor.node_order.iter().position(|a| a == node_id) {
+            editor.node_order.remove(x);
+        }
+    }
+    editor.selected_nodes.clear();
+
+    let group = GroupData {
+        name: "Group".to_string(),
+        graph: inner,
+        positions,
+        inputs,
+        outputs,
+    };
+    let node_id = add_group_node(editor, group.clone(), center.to_pos2());
+    for ((name, _), source) in group.inputs.iter().zip(input_sources) {
+        if let Ok(input_id) = editor.graph[node_id].get_input(name) {
+            editor.graph.add_connection(source, input_id);
+        }
+    }
+    for ((name, _), targets) in group.outputs.iter().zip(output_targets) {
+        if let Ok(output_id) = editor.graph[node_id].get_output(name) {
+            for target in targets {
+                editor.graph.add_connection(output_id, target);
+            }
+        }
+    }
+    editor.selected_nodes.push(node_id);
+    Ok(node_id)
+}
+
+/// Swaps the editor over to the contents of the group node `node_id`.
+pub fn open_group(state: &mut SoundNodeGraphState, node_id: NodeId) {
+    let group = match state
+        .editor_state
+        .graph
+        .nodes
+        .get(node_id)
+        .and_then(|x| x.user_data.group.clone())
+    {
+        Some(x) => x,
+        None => return,
+    };
+    let mut editor = SoundGraphEditorState::default();
+    editor.graph = group.graph;
+    for node_id in editor.graph.iter_nodes().collect::<Vec<_>>() {
+        let position = group
+            .positions
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, pos)| *pos)
+            .unwrap_or_default();
+        editor.node_order.push(node_id);
+        editor.node_positions.insert(node_id, position);
+    }
+    let parent = std::mem::replace(&mut state.editor_state, editor);
+    state.group_stack.push(GroupFrame {
+        node_id,
+        name: group.name,
+        parent,
+    });
+}
+
+/// Returns from the group being edited to its parent graph, writing the edits back into the
+/// group node.
+pub fn close_group(state: &mut SoundNodeGraphState) {
+    let frame = match state.group_stack.pop() {
+        Some(x) => x,
+        None => return,
+    };
+    let editor = std::mem::replace(&mut state.editor_state, frame.parent);
+    let graph = &mut state.editor_state.graph;
+    let node = match graph.nodes.get_mut(frame.node_id) {
+        Some(x) => x,
+        None => return,
+    };
+    node.label = frame.name.clone();
+    node.user_data.name = frame.name.clone();
+    let mut group = match node.user_data.group.take() {
+        Some(x) => x,
+        None => return,
+    };
+    group.name = frame.name;
+    group.positions = editor
+        .node_order
+        .iter()
+        .filter_map(|x| editor.node_positions.get(*x).map(|pos| (*x, *pos)))
+        .collect();
+    group
+        .inputs
+        .retain(|(_, x)| editor.graph.inputs.contains_key(*x));
+    group
+        .outputs
+        .retain(|(_, x)| editor.graph.outputs.contains_key(*x));
+    group.graph = editor.graph;
+    sync_group_params(graph, frame.node_id, &group);
+    graph[frame.node_id].user_data.group = Some(group);
+}
+
+/// Closes every open group, so the editor is back on the top level graph.
+pub fn close_all_groups(state: &mut SoundNodeGraphState) {
+    while !state.group_stack.is_empty() {
+        close_group(state);
+    }
+}
+
+impl SoundNodeGraphState {
+    /// The top level graph with any group currently being edited folded back into it. Use this
+    /// instead of `editor_state.graph` when evaluating the patch.
+    pub fn root_graph(&self) -> Graph<NodeData, DataType, ValueType> {
+        let mut graph = self.editor_state.graph.clone();
+        for frame in self.group_stack.iter().rev() {
+            let mut parent = frame.parent.graph.clone();
+            if let Some(group) = parent
+                .nodes
+                .get_mut(frame.node_id)
+                .and_then(|x| x.user_data.group.as_mut())
+            {
+                group.graph = graph;
+            }
+            graph = parent;
+        }
+        graph
+    }
+}
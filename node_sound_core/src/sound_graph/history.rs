@@ -0,0 +1,186 @@
+use super::graph::{NodeData, SoundGraphEditorState, SoundNodeGraphState};
+use super::graph_types::{DataType, ValueType};
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers, Pos2};
+use egui_node_graph_2::{Graph, NodeId};
+
+const MAX_HISTORY: usize = 100;
+
+pub const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+pub const REDO_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(Modifiers::COMMAND.plus(Modifiers::SHIFT), Key::Z);
+
+/// A serialized graph and where its nodes were. Kept apart so moving nodes around can be undone
+/// without counting as a change to the patch.
+#[derive(Clone, PartialEq)]
+struct Snapshot {
+    graph: String,
+    positions: Vec<(NodeId, Pos2)>,
+}
+
+/// Undo and redo stacks of serialized graph snapshots. A new entry is only recorded once the
+/// pointer is released, so dragging a knob or a node ends up as a single entry. Snapshots are
+/// only taken after something could have changed the graph, not on every repaint.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    current: Option<Snapshot>,
+    group_depth: usize,
+    revision: usize,
+    dirty: bool,
+}
+
+impl History {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Bumped whenever the graph changes, including through undo and redo. Moving nodes doesn't
+    /// count, so it only changes when the patch sounds different.
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// Has the next `record_history` take a snapshot, for changes that don't come from the
+    /// user's input, like loading a patch.
+    pub fn mark_changed(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.current = None;
+    }
+}
+
+fn snapshot(editor: &SoundGraphEditorState) -> Snapshot {
+    Snapshot {
+        graph: ron::ser::to_string(&editor.graph).unwrap_or_default(),
+        positions: editor
+            .node_order
+            .iter()
+            .filter_map(|x| editor.node_positions.get(*x).map(|pos| (*x, *pos)))
+            .collect(),
+    }
+}
+
+fn restore(editor: &mut SoundGraphEditorState, snapshot: &Snapshot) -> bool {
+    let graph: Graph<NodeData, DataType, ValueType> = match ron::de::from_str(&snapshot.graph) {
+        Ok(x) => x,
+        Err(_x) => return false,
+    };
+    editor.graph = graph;
+    editor.node_order.clear();
+    editor.node_positions.clear();
+    for (node_id, position) in snapshot.positions.iter() {
+        editor.node_order.push(*node_id);
+        editor.node_positions.insert(*node_id, *position);
+    }
+    editor.selected_nodes.clear();
+    true
+}
+
+/// Whether going from `from` to `to` changes more than the layout.
+fn changes_graph(from: Option<&Snapshot>, to: &Snapshot) -> bool {
+    from.is_none_or(|x| x.graph != to.graph)
+}
+
+impl SoundNodeGraphState {
+    pub fn undo(&mut self) {
+        let history = &mut self.user_state.history;
+        let previous = match history.undo.pop() {
+            Some(x) => x,
+            None => return,
+        };
+        if restore(&mut self.editor_state, &previous) {
+            if changes_graph(history.current.as_ref(), &previous) {
+                history.revision += 1;
+            }
+            if let Some(current) = history.current.replace(previous) {
+                history.redo.push(current);
+            }
+            self.user_state.is_saved = false;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        let history = &mut self.user_state.history;
+        let next = match history.redo.pop() {
+            Some(x) => x,
+            None => return,
+        };
+        if restore(&mut self.editor_state, &next) {
+            if changes_graph(history.current.as_ref(), &next) {
+                history.revision += 1;
+            }
+            if let Some(current) = history.current.replace(next) {
+                history.undo.push(current);
+            }
+            self.user_state.is_saved = false;
+        }
+    }
+
+    /// Records the graph as a new history entry if it changed since the last one. Opening or
+    /// closing a group starts a fresh history, since entries only describe one graph level.
+    pub fn record_history(&mut self, ctx: &egui::Context) {
+        let history = &mut self.user_state.history;
+        // Clicks, drags and typing are the only input that edits the graph.
+        history.dirty |= ctx.input(|i| {
+            i.pointer.any_released()
+                || i.events.iter().any(|x| {
+                    matches!(
+                        x,
+                        egui::Event::Key { pressed: true, .. }
+                            | egui::Event::Text(_)
+                            | egui::Event::Paste(_)
+                    )
+                })
+        });
+        if ctx.input(|i| i.pointer.any_down()) {
+            return;
+        }
+        if history.group_depth != self.group_stack.len() {
+            history.clear();
+            history.group_depth = self.group_stack.len();
+            history.revision += 1;
+            history.dirty = true;
+        }
+        if !history.dirty && history.current.is_some() {
+            return;
+        }
+        history.dirty = false;
+        let current = snapshot(&self.editor_state);
+        match history.current.take() {
+            Some(previous) if previous != current => {
+                if changes_graph(Some(&previous), &current) {
+                    history.revision += 1;
+                }
+                history.undo.push(previous);
+                if history.undo.len() > MAX_HISTORY {
+                    history.undo.remove(0);
+                }
+                history.redo.clear();
+            }
+            _ => {}
+        }
+        history.current = Some(current);
+    }
+}
+
+/// Handles the undo and redo shortcuts, leaving them to text fields that have focus.
+pub fn handle_shortcuts(ctx: &egui::Context, state: &mut SoundNodeGraphState) {
+    if ctx.wants_keyboard_input() {
+        return;
+    }
+    // Checked first as the undo shortcut also matches with shift held.
+    if ctx.input_mut(|i| i.consume_shortcut(&REDO_SHORTCUT)) {
+        state.redo();
+    } else if ctx.input_mut(|i| i.consume_shortcut(&UNDO_SHORTCUT)) {
+        state.undo();
+    }
+}
@@ -0,0 +1,402 @@
+use super::graph::{FileManager, NodeId, SoundNodeGraphState};
+use super::graph_types::ValueType;
+use super::note::{NoteSpeed, Pitch};
+use crate::constants::WAVE_TABLE_SIZE;
+use crate::sounds::step_sequencer::SequencerStep;
+use crate::sounds::tracker::{TrackerNote, tracker_lanes};
+use std::str::FromStr;
+
+/// Divisible by three so triplets land on whole ticks.
+const TICKS_PER_BEAT: u16 = 960;
+const MICROSECONDS_PER_MINUTE: f32 = 60_000_000.0;
+/// The key the step sequencer's gates are written on.
+const SEQUENCER_NOTE: u8 = 60;
+const SEQUENCER_VELOCITY: u8 = 100;
+
+/// The midi note number of a step, none for a rest.
+fn midi_note(tracker_note: &TrackerNote) -> Option<u8> {
+    let note = tracker_note.note.clone()?;
+    Some(
+        Pitch(tracker_note.octave.clone(), note)
+            .midi_note()
+            .min(127) as u8,
+    )
+}
+
+/// The largest number a midi variable length quantity can hold.
+const MAX_VAR_LEN: u32 = 0x0fff_ffff;
+
+fn write_var_len(bytes: &mut Vec<u8>, value: u32) {
+    let mut value = value.min(MAX_VAR_LEN);
+    let mut buffer = [0u8; 4];
+    let mut len = 0;
+    loop {
+        buffer[len] = (value & 0x7f) as u8;
+        len += 1;
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+    }
+    for idx in (0..len).rev() {
+        bytes.push(buffer[idx] | if idx > 0 { 0x80 } else { 0 });
+    }
+}
+
+/// Wraps events given as `(tick, bytes)` into a track chunk, ending it at `end`.
+fn track_chunk(events: &[(u64, Vec<u8>)], end: u64) -> Vec<u8> {
+    let mut data = vec![];
+    let mut last = 0;
+    for (tick, event) in events.iter() {
+        write_var_len(&mut data, (tick - last) as u32);
+        data.extend_from_slice(event);
+        last = *tick;
+    }
+    write_var_len(&mut data, end.saturating_sub(last) as u32);
+    data.extend_from_slice(&[0xff, 0x2f, 0x00]);
+    let mut chunk = b"MTrk".to_vec();
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(&data);
+    chunk
+}
+
+fn track_name(name: &str) -> Vec<u8> {
+    let mut event = vec![0xff, 0x03];
+    write_var_len(&mut event, name.len() as u32);
+    event.extend_from_slice(name.as_bytes());
+    event
+}
+
+/// The note events of one lane and the tick it ends on. Tied steps carry on the note before
+/// them rather than playing it again.
+fn lane_events(notes: &[TrackerNote]) -> (Vec<(u64, Vec<u8>)>, u64) {
+    let mut events = vec![];
+    let mut beats = 0.0;
+    let mut sounding: Option<u8> = None;
+    for tracker_note in notes.iter() {
+        let tick = (beats * TICKS_PER_BEAT as f64).round() as u64;
+        beats += tracker_note.speed.get_beats() as f64;
+        let note = midi_note(tracker_note);
+        if tracker_note.tie && sounding.is_some() && (note.is_none() || note == sounding) {
+            continue;
+        }
+        if let Some(off) = sounding.take() {
+            events.push((tick, vec![0x80, off, 0]));
+        }
+        if let Some(on) = note {
+            let velocity = (tracker_note.velocity * 127.0).round().clamp(1.0, 127.0) as u8;
+            events.push((tick, vec![0x90, on, velocity]));
+            sounding = Some(on);
+        }
+    }
+    let end = (beats * TICKS_PER_BEAT as f64).round() as u64;
+    if let Some(off) = sounding {
+        events.push((end, vec![0x80, off, 0]));
+    }
+    (events, end)
+}
+
+/// A type 1 standard midi file with a tempo track followed by `tracks`, each given as its name,
+/// its events and the tick it ends on.
+fn smf(tracks: Vec<(String, Vec<(u64, Vec<u8>)>, u64)>, bpm: f32) -> Vec<u8> {
+    let mut bytes = b"MThd".to_vec();
+    bytes.extend_from_slice(&6u32.to_be_bytes());
+    bytes.extend_from_slice(&1u16.to_be_bytes());
+    bytes.extend_from_slice(&(tracks.len() as u16 + 1).to_be_bytes());
+    bytes.extend_from_slice(&TICKS_PER_BEAT.to_be_bytes());
+    let tempo = (MICROSECONDS_PER_MINUTE / bpm.max(1.0)) as u32;
+    let tempo = tempo.to_be_bytes();
+    bytes.extend(track_chunk(
+        &[
+            (0, track_name("tempo")),
+            (0, vec![0xff, 0x51, 0x03, tempo[1], tempo[2], tempo[3]]),
+            (0, vec![0xff, 0x58, 0x04, 4, 2, 24, 8]),
+        ],
+        0,
+    ));
+    for (name, mut events, end) in tracks {
+        events.insert(0, (0, track_name(&name)));
+        bytes.extend(track_chunk(&events, end));
+    }
+    bytes
+}
+
+/// A type 1 standard midi file of a tracker pattern, with a tempo track and a track per lane.
+pub fn tracker_midi(notes: &[TrackerNote], bpm: f32) -> Vec<u8> {
+    let tracks = tracker_lanes(notes)
+        .iter()
+        .enumerate()
+        .map(|(idx, lane)| {
+            let (events, end) = lane_events(lane);
+            (format!("lane {}", idx + 1), events, end)
+        })
+        .collect();
+    smf(tracks, bpm)
+}
+
+/// A type 1 standard midi file of one loop of a step sequencer's gates, as notes on middle C.
+/// Steps left to chance are written the way they play on the first loop, and ratchets as
+/// repeated notes.
+pub fn sequencer_midi(
+    steps: &[SequencerStep],
+    speed: &NoteSpeed,
+    gate_length: f32,
+    bpm: f32,
+) -> Vec<u8> {
+    let step_ticks = speed.get_beats() as f64 * TICKS_PER_BEAT as f64;
+    let mut events = vec![];
+    for (idx, step) in steps.iter().enumerate() {
+        if !step.opens(idx as u64) || gate_length <= 0.0 {
+            continue;
+        }
+        let ratchet_ticks = step_ticks / step.ratchets.max(1) as f64;
+        for ratchet in 0..step.ratchets.max(1) {
+            let start = idx as f64 * step_ticks + ratchet as f64 * ratchet_ticks;
+            let on = start.round() as u64;
+            let off = (start + ratchet_ticks * gate_length.min(1.0) as f64).round() as u64;
+            events.push((on, vec![0x90, SEQUENCER_NOTE, SEQUENCER_VELOCITY]));
+            events.push((off.max(on + 1), vec![0x80, SEQUENCER_NOTE, 0]));
+        }
+    }
+    let end = (steps.len() as f64 * step_ticks).round() as u64;
+    smf(vec![("gates".to_string(), events, end)], bpm)
+}
+
+impl FileManager {
+    /// Asks where to save a pattern that was requested from a tracker or step sequencer's Export
+    /// MIDI button.
+    pub fn open_midi_export_dialog(&mut self) {
+        if let Some(node_id) = self.midi_export_active {
+            self.midi_export_path = rfd::FileDialog::new()
+                .add_filter("midi", &["mid"])
+                .set_file_name("pattern.mid")
+                .save_file()
+                .map(|x| (x, node_id));
+            self.midi_export_active = None;
+        }
+    }
+}
+
+fn node_tracker_notes(state: &SoundNodeGraphState, node_id: NodeId) -> Option<Vec<TrackerNote>> {
+    let graph = &state.editor_state.graph;
+    graph.nodes.get(node_id)?.inputs.iter().find_map(|(_, id)| {
+        match &graph.inputs.get(*id)?.value {
+            ValueType::TrackerNotes { notes } => Some(notes.clone()),
+            _ => None,
+        }
+    })
+}
+
+/// The steps, step length and gate length of the step sequencer `node_id`, from its constant
+/// inputs.
+fn node_sequencer(
+    state: &SoundNodeGraphState,
+    node_id: NodeId,
+) -> Option<(Vec<SequencerStep>, NoteSpeed, f32)> {
+    let graph = &state.editor_state.graph;
+    let node = graph.nodes.get(node_id)?;
+    let value = |name: &str| Some(&graph.inputs.get(node.get_input(name).ok()?)?.value);
+    let count = match value("steps")? {
+        ValueType::Dropdown { value, .. } => value.parse::<usize>().ok()?,
+        _ => return None,
+    };
+    let speed = match value("note speed")? {
+        ValueType::Dropdown { value, .. } => NoteSpeed::from_str(value).ok()?,
+        _ => return None,
+    };
+    let table = |name: &str| match value(name)? {
+        ValueType::Graph { value, .. } => Some(value.clone().unwrap_or(vec![0.0; WAVE_TABLE_SIZE])),
+        _ => None,
+    };
+    let gate_length = match value("gate length")? {
+        ValueType::Float { value, .. } => *value,
+        _ => return None,
+    };
+    let steps = SequencerStep::from_tables(
+        count,
+        &table("values")?,
+        &table("probability")?,
+        &table("ratchets")?,
+    );
+    Some((steps, speed, gate_length))
+}
+
+/// Writes the pattern being exported once its file dialog has closed.
+pub fn handle_midi_export(state: &mut SoundNodeGraphState) {
+    let path = match state.user_state.files.try_lock() {
+        Ok(mut files) => {
+            if files.midi_export_active.is_some() {
+                return;
+            }
+            files.midi_export_path.take()
+        }
+        Err(_x) => return,
+    };
+    if let Some((path, node_id)) = path {
+        let bpm = state._unserializeable_state.queue.get_bpm().load();
+        let bytes = match node_tracker_notes(state, node_id) {
+            Some(notes) => tracker_midi(&notes, bpm),
+            None => match node_sequencer(state, node_id) {
+                Some((steps, speed, gate_length)) => {
+                    sequencer_midi(&steps, &speed, gate_length, bpm)
+                }
+                None => return,
+            },
+        };
+        match std::fs::write(&path, bytes) {
+            Ok(_) => state._unserializeable_state.error = None,
+            Err(err) => {
+                state._unserializeable_state.error = Some(format!("MIDI export failed: {}", err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound_graph::note::{Note, Octave};
+    use crate::sounds::midi::{MidiNote, midi_notes, read_tempo_changes};
+    use synthrs::midi::read_midi_file;
+
+    fn step(speed: NoteSpeed, note: Option<Note>, octave: Octave, tie: bool) -> TrackerNote {
+        TrackerNote {
+            octave,
+            tie,
+            ..TrackerNote::new(speed, note)
+        }
+    }
+
+    fn var_len(value: u32) -> Vec<u8> {
+        let mut bytes = vec![];
+        write_var_len(&mut bytes, value);
+        bytes
+    }
+
+    /// Writes `bytes` to a file of its own and reads it back as `(tick, bpm)` tempo changes and
+    /// notes sorted by start and key.
+    fn read_back(name: &str, bytes: &[u8]) -> (Vec<(u64, f64)>, Vec<MidiNote>) {
+        let path = std::env::temp_dir().join(format!("node_sound_{}.mid", name));
+        std::fs::write(&path, bytes).unwrap();
+        let path = path.to_str().unwrap().to_string();
+        let tempo = read_tempo_changes(&path).unwrap();
+        let song = match read_midi_file(path.clone()) {
+            Ok(x) => x,
+            Err(_x) => panic!("couldn't read {}", path),
+        };
+        let mut notes = midi_notes(&song);
+        notes.sort_by(|a, b| a.start.total_cmp(&b.start).then(a.note.cmp(&b.note)));
+        (tempo, notes)
+    }
+
+    #[test]
+    fn var_len_boundaries() {
+        assert_eq!(var_len(0), vec![0x00]);
+        assert_eq!(var_len(0x7f), vec![0x7f]);
+        assert_eq!(var_len(0x80), vec![0x81, 0x00]);
+        assert_eq!(var_len(0x3fff), vec![0xff, 0x7f]);
+        assert_eq!(var_len(0x4000), vec![0x81, 0x80, 0x00]);
+        assert_eq!(var_len(MAX_VAR_LEN), vec![0xff, 0xff, 0xff, 0x7f]);
+        assert_eq!(var_len(u32::MAX), vec![0xff, 0xff, 0xff, 0x7f]);
+    }
+
+    #[test]
+    fn ties_carry_notes_across_rests() {
+        let notes = [
+            step(NoteSpeed::Quarter, Some(Note::C), Octave::O4, false),
+            step(NoteSpeed::Quarter, None, Octave::O4, true),
+            step(NoteSpeed::Quarter, Some(Note::D), Octave::O4, false),
+            step(NoteSpeed::Quarter, Some(Note::D), Octave::O4, true),
+            step(NoteSpeed::Quarter, None, Octave::O4, false),
+            // Nothing is sounding, so the tie is ignored.
+            step(NoteSpeed::Quarter, Some(Note::E), Octave::O4, true),
+        ];
+        let (events, end) = lane_events(&notes);
+        assert_eq!(
+            events,
+            vec![
+                (0, vec![0x90, 60, 127]),
+                (1920, vec![0x80, 60, 0]),
+                (1920, vec![0x90, 62, 127]),
+                (3840, vec![0x80, 62, 0]),
+                (4800, vec![0x90, 64, 127]),
+                (5760, vec![0x80, 64, 0]),
+            ]
+        );
+        assert_eq!(end, 5760);
+    }
+
+    #[test]
+    fn tracker_pattern_round_trips() {
+        let mut quiet = step(NoteSpeed::Sixteenth, Some(Note::E), Octave::O4, false);
+        quiet.velocity = 0.5;
+        let mut bass = step(NoteSpeed::Half, Some(Note::G), Octave::O3, false);
+        bass.lane = 1;
+        let pattern = [
+            step(NoteSpeed::Quarter, Some(Note::C), Octave::O4, false),
+            step(NoteSpeed::Eighth, None, Octave::O4, true),
+            step(NoteSpeed::Quarter, None, Octave::O4, false),
+            quiet,
+            bass,
+        ];
+        let bytes = tracker_midi(&pattern, 90.0);
+        assert_eq!(&bytes[..4], b"MThd");
+        // Type 1, a tempo track and two lanes.
+        assert_eq!(&bytes[8..12], &[0, 1, 0, 3]);
+        let (tempo, notes) = read_back("tracker_round_trip", &bytes);
+        assert_eq!(tempo.len(), 1);
+        assert_eq!(tempo[0].0, 0);
+        assert!((tempo[0].1 - 90.0).abs() < 0.001);
+        let notes = notes
+            .iter()
+            .map(|x| {
+                (
+                    x.start,
+                    x.length,
+                    x.note,
+                    (x.velocity * 127.0).round() as u8,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            vec![
+                (0.0, 2.0, 55, 127),
+                (0.0, 1.5, 60, 127),
+                (2.5, 0.25, 64, 64)
+            ]
+        );
+    }
+
+    #[test]
+    fn sequencer_ratchets_round_trip() {
+        let steps = [
+            SequencerStep {
+                value: 0.0,
+                probability: 1.0,
+                ratchets: 2,
+            },
+            SequencerStep {
+                value: 0.0,
+                probability: 0.0,
+                ratchets: 1,
+            },
+            SequencerStep {
+                value: 0.0,
+                probability: 1.0,
+                ratchets: 1,
+            },
+        ];
+        let bytes = sequencer_midi(&steps, &NoteSpeed::Quarter, 0.5, 120.0);
+        let (tempo, notes) = read_back("sequencer_round_trip", &bytes);
+        assert_eq!(tempo, vec![(0, 120.0)]);
+        let notes = notes
+            .iter()
+            .map(|x| (x.start, x.length, x.note))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            notes,
+            vec![(0.0, 0.25, 60), (0.5, 0.25, 60), (2.0, 0.5, 60)]
+        );
+    }
+}
@@ -1,7 +1,21 @@
+pub mod arpeggiator;
+pub mod audition;
+pub mod automation;
+pub mod clipboard;
+pub mod compiler;
 pub mod copy_paste_del_helpers;
 mod float_selector;
 pub mod graph;
 pub mod graph_types;
+pub mod groups;
+pub mod history;
+pub mod midi_export;
+pub mod node_finder;
 pub mod note;
+pub mod patch_files;
+pub mod scope;
 pub mod themes;
+mod tracker_grid;
+pub mod tuning;
+pub mod voice_mode;
 pub mod wave_table_graph;
@@ -0,0 +1,191 @@
+use super::graph::{
+    NodeDefinitionUi, NodeDefinitionsUi, NodeTemplateIter, NodeTemplateTrait, SoundGraphUserState,
+    SoundNodeGraphState,
+};
+use crate::nodes::NodeCategory;
+use eframe::egui;
+
+const MAX_RECENT_NODES: usize = 10;
+
+#[derive(Default)]
+pub struct NodeFinder {
+    pub open: bool,
+    pub filter: String,
+}
+
+/// Scores how well `query` matches `text` as a case-insensitive subsequence, or `None` if it
+/// doesn't match at all. Consecutive characters and characters at the start of a word score
+/// higher, so "sw" prefers "Sine Wave" over "Automated Sawtooth Wave".
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|i| text[*i] == c)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 8;
+        }
+        match previous {
+            Some(x) if x + 1 == found => score += 5,
+            Some(x) => score -= (found - x) as i32 / 4,
+            None => score -= found as i32 / 4,
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+fn match_score(template: &NodeDefinitionUi, filter: &str) -> Option<i32> {
+    let name = fuzzy_score(filter, &template.0.name);
+    let tooltip = template.0.tooltip.to_lowercase();
+    if tooltip.contains(&filter.to_lowercase()) {
+        return name.max(Some(0));
+    }
+    name
+}
+
+/// Moves `name` to the front of the recently used nodes.
+pub fn remember_recent(user_state: &mut SoundGraphUserState, name: &str) {
+    user_state.recent_nodes.retain(|x| x != name);
+    user_state.recent_nodes.insert(0, name.to_string());
+    user_state.recent_nodes.truncate(MAX_RECENT_NODES);
+}
+
+fn toggle_favourite(user_state: &mut SoundGraphUserState, name: &str) {
+    match user_state.favourite_nodes.iter().position(|x| x == name) {
+        Some(x) => {
+            user_state.favourite_nodes.remove(x);
+        }
+        None => user_state.favourite_nodes.push(name.to_string()),
+    }
+}
+
+fn node_row(
+    ui: &mut egui::Ui,
+    template: &NodeDefinitionUi,
+    user_state: &SoundGraphUserState,
+    add: &mut Option<NodeDefinitionUi>,
+    favourite: &mut Option<String>,
+) {
+    ui.horizontal(|ui| {
+        let is_favourite = user_state.favourite_nodes.contains(&template.0.name);
+        if ui
+            .small_button(if is_favourite { "★" } else { "☆" })
+            .on_hover_text("Toggle favourite")
+            .clicked()
+        {
+            *favourite = Some(template.0.name.clone());
+        }
+        if ui
+            .selectable_label(false, &template.0.name)
+            .on_hover_text(&template.0.tooltip)
+            .clicked()
+        {
+            *add = Some(template.clone());
+        }
+    });
+}
+
+/// A side panel listing every node by category, with a fuzzy search box and the user's
+/// favourite and recently used nodes at the top. Clicking a node adds it to the middle of the
+/// graph view.
+pub fn node_finder(ctx: &egui::Context, state: &mut SoundNodeGraphState) {
+    if !state._unserializeable_state.node_finder.open {
+        return;
+    }
+
+    let templates = NodeDefinitionsUi(
+        &state._unserializeable_state.node_definitions,
+        &state._unserializeable_state.group_library,
+    )
+    .all_kinds();
+    let find = |name: &String| templates.iter().find(|x| x.0.name == *name);
+
+    let mut add = None;
+    let mut favourite = None;
+    let finder = &mut state._unserializeable_state.node_finder;
+    let user_state = &state.user_state;
+    egui::SidePanel::right("node_finder")
+        .resizable(true)
+        .default_width(200.0)
+        .show(ctx, |ui| {
+            ui.heading("Nodes");
+            ui.horizontal(|ui| {
+                ui.label("search");
+                ui.text_edit_singleline(&mut finder.filter);
+            });
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let filter = finder.filter.trim();
+                if !filter.is_empty() {
+                    let mut matches = templates
+                        .iter()
+                        .filter_map(|x| Some((match_score(x, filter)?, x)))
+                        .collect::<Vec<_>>();
+                    matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.0.name.cmp(&b.1.0.name)));
+                    if matches.is_empty() {
+                        ui.label("No matching nodes.");
+                    }
+                    for (_, template) in matches {
+                        node_row(ui, template, user_state, &mut add, &mut favourite);
+                    }
+                    return;
+                }
+
+                if !user_state.favourite_nodes.is_empty() {
+                    ui.label(egui::RichText::new("Favourites").strong());
+                    for template in user_state.favourite_nodes.iter().filter_map(find) {
+                        node_row(ui, template, user_state, &mut add, &mut favourite);
+                    }
+                    ui.separator();
+                }
+                if !user_state.recent_nodes.is_empty() {
+                    ui.label(egui::RichText::new("Recent").strong());
+                    for template in user_state.recent_nodes.iter().filter_map(find) {
+                        node_row(ui, template, user_state, &mut add, &mut favourite);
+                    }
+                    ui.separator();
+                }
+                for category in NodeCategory::ALL {
+                    let mut in_category = templates
+                        .iter()
+                        .filter(|x| x.0.category == category)
+                        .collect::<Vec<_>>();
+                    if in_category.is_empty() {
+                        continue;
+                    }
+                    in_category.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+                    egui::CollapsingHeader::new(format!("{:?}", category))
+                        .default_open(false)
+                        .show(ui, |ui| {
+                            for template in in_category {
+                                node_row(ui, template, user_state, &mut add, &mut favourite);
+                            }
+                        });
+                }
+            });
+        });
+
+    if let Some(name) = favourite {
+        toggle_favourite(&mut state.user_state, &name);
+    }
+    if let Some(template) = add {
+        let user_state = &mut state.user_state;
+        let editor = &mut state.editor_state;
+        let node_id = editor.graph.add_node(
+            template.node_graph_label(user_state),
+            template.tooltip(),
+            template.user_data(user_state),
+            |graph, node_id| template.build_node(graph, user_state, node_id),
+        );
+        editor.node_order.push(node_id);
+        editor
+            .node_positions
+            .insert(node_id, ctx.screen_rect().center() - editor.pan_zoom.pan);
+        editor.selected_nodes = vec![node_id];
+        remember_recent(user_state, &template.0.name);
+    }
+}
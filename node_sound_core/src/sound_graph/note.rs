@@ -227,6 +227,13 @@ impl Pitch {
         5587.65, 5919.91, 6271.93, 6644.88, 7040.0, 7458.62, 7902.13,
     ];
 
+    /// The midi key of the pitch, with C4 on middle C.
+    pub fn midi_note(&self) -> i32 {
+        let octave = Octave::ALL.iter().position(|x| *x == self.0).unwrap_or(0);
+        let note = Note::ALL.iter().position(|x| *x == self.1).unwrap_or(0);
+        ((octave + 1) * 12 + note) as i32
+    }
+
     pub fn match_freq(&self) -> f32 {
         match self.clone() {
             Pitch(Octave::O0, Note::C) => 16.35,
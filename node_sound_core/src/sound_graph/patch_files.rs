@@ -0,0 +1,352 @@
+use super::arpeggiator::ArpSettings;
+use super::automation::AutomationMacros;
+use super::copy_paste_del_helpers::{ClipboardData, copy, delete_nodes, paste};
+use super::graph::{FileManager, SoundNodeGraphState};
+use super::groups;
+use super::tuning::Tuning;
+use super::voice_mode::VoiceSettings;
+use eframe::egui;
+use futures::executor;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PATCH_EXTENSION: &str = "nsp";
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct PatchMetadata {
+    pub name: String,
+    pub author: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PatchFile {
+    pub metadata: PatchMetadata,
+    pub data: ClipboardData,
+    /// Missing from patches saved before macros could be named.
+    #[serde(default)]
+    pub macros: Option<AutomationMacros>,
+    #[serde(default)]
+    pub arpeggiator: Option<ArpSettings>,
+    #[serde(default)]
+    pub tuning: Option<Tuning>,
+    #[serde(default)]
+    pub voices: Option<VoiceSettings>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PresetEntry {
+    pub path: PathBuf,
+    pub metadata: PatchMetadata,
+}
+
+impl PresetEntry {
+    fn matches(&self, filter: &str) -> bool {
+        let filter = filter.trim().to_lowercase();
+        if filter.is_empty() {
+            return true;
+        }
+        self.metadata.name.to_lowercase().contains(&filter)
+            || self.metadata.author.to_lowercase().contains(&filter)
+            || self
+                .metadata
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&filter))
+    }
+}
+
+#[derive(Default)]
+pub struct PresetBrowser {
+    pub open: bool,
+    pub entries: Vec<PresetEntry>,
+    pub filter: String,
+    pub tags_input: String,
+    scanned: bool,
+}
+
+impl PresetBrowser {
+    pub fn refresh(&mut self) {
+        self.scanned = true;
+        self.entries = match preset_folder() {
+            Some(folder) => scan_presets(&folder),
+            None => vec![],
+        };
+    }
+}
+
+/// The folder user presets are saved to and listed from in the preset browser.
+pub fn preset_folder() -> Option<PathBuf> {
+    dirs::data_dir().map(|x| x.join("node_sound").join("presets"))
+}
+
+pub fn read_patch(path: &Path) -> Result<PatchFile, Box<dyn std::error::Error>> {
+    Ok(ron::de::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn write_patch(path: &Path, patch: &PatchFile) -> Result<(), Box<dyn std::error::Error>> {
+    fs::write(
+        path,
+        ron::ser::to_string_pretty(patch, ron::ser::PrettyConfig::default())?,
+    )?;
+    Ok(())
+}
+
+pub fn scan_presets(folder: &Path) -> Vec<PresetEntry> {
+    let dir = match fs::read_dir(folder) {
+        Ok(x) => x,
+        Err(_x) => return vec![],
+    };
+    let mut entries = dir
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .map(|x| x == PATCH_EXTENSION)
+                .unwrap_or(false)
+        })
+        .filter_map(|path| {
+            let metadata = read_patch(&path).ok()?.metadata;
+            Some(PresetEntry { path, metadata })
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| {
+        a.metadata
+            .name
+            .to_lowercase()
+            .cmp(&b.metadata.name.to_lowercase())
+    });
+    entries
+}
+
+pub fn save_patch(
+    state: &mut SoundNodeGraphState,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // The editor only holds the inside of an open group, the patch is the whole graph.
+    groups::close_all_groups(state);
+    let patch = PatchFile {
+        metadata: state.user_state.patch_metadata.clone(),
+        data: copy(&mut state.editor_state, true),
+        macros: Some(state.user_state.automation_macros.clone()),
+        arpeggiator: Some(state.user_state.arpeggiator.clone()),
+        tuning: Some(state.user_state.tuning.clone()),
+        voices: Some(state.user_state.voices.clone()),
+    };
+    write_patch(path, &patch)?;
+    state.user_state.is_saved = true;
+    Ok(())
+}
+
+pub fn load_patch(
+    state: &mut SoundNodeGraphState,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let patch = read_patch(path)?;
+    groups::close_all_groups(state);
+    delete_nodes(&mut state.editor_state, true);
+    state.editor_state.selected_nodes.clear();
+    executor::block_on(paste(&mut state.editor_state, None, patch.data));
+    state.editor_state.selected_nodes.clear();
+    state._unserializeable_state.preset_browser.tags_input = patch.metadata.tags.join(", ");
+    state.user_state.patch_metadata = patch.metadata;
+    if let Some(macros) = patch.macros {
+        state.user_state.automation_macros = macros;
+    }
+    if let Some(arpeggiator) = patch.arpeggiator {
+        state.user_state.arpeggiator = ArpSettings {
+            loaded: true,
+            ..arpeggiator
+        };
+    }
+    if let Some(voices) = patch.voices {
+        state.user_state.voices = VoiceSettings {
+            loaded: true,
+            ..voices
+        };
+    }
+    // Patches saved before tuning was added are in 12 tone equal temperament.
+    state.user_state.tuning = Tuning {
+        open: state.user_state.tuning.open,
+        ..patch.tuning.unwrap_or_default()
+    };
+    state.user_state.is_saved = true;
+    state.user_state.history.mark_changed();
+    Ok(())
+}
+
+fn preset_file_name(metadata: &PatchMetadata) -> String {
+    let name: String = metadata
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = name.trim();
+    format!(
+        "{}.{}",
+        if name.is_empty() { "patch" } else { name },
+        PATCH_EXTENSION
+    )
+}
+
+impl FileManager {
+    /// Opens the save/open patch dialogs that were requested from the editor. The plugins call
+    /// this from a background task, the standalone app calls it directly.
+    pub fn open_patch_dialogs(&mut self) {
+        if self.patch_save_active {
+            self.patch_save_active = false;
+            let mut dialog = rfd::FileDialog::new().add_filter("patch", &[PATCH_EXTENSION]);
+            if let Some(folder) = preset_folder() {
+                dialog = dialog.set_directory(folder);
+            }
+            self.patch_save_path = dialog
+                .set_file_name(format!("patch.{}", PATCH_EXTENSION))
+                .save_file();
+        }
+        if self.patch_open_active {
+            self.patch_open_active = false;
+            let mut dialog = rfd::FileDialog::new().add_filter("patch", &[PATCH_EXTENSION]);
+            if let Some(folder) = preset_folder() {
+                dialog = dialog.set_directory(folder);
+            }
+            self.patch_open_path = dialog.pick_file();
+        }
+    }
+
+    pub fn has_patch_dialog(&self) -> bool {
+        self.patch_save_active || self.patch_open_active
+    }
+}
+
+/// Saves or loads any patch paths the file dialogs have returned since the last frame.
+pub fn handle_patch_files(state: &mut SoundNodeGraphState) {
+    let (save_path, open_path) = match state.user_state.files.try_lock() {
+        Ok(mut files) => (files.patch_save_path.take(), files.patch_open_path.take()),
+        Err(_x) => return,
+    };
+    if let Some(path) = save_path {
+        match save_patch(state, &path) {
+            Ok(_) => state._unserializeable_state.error = None,
+            Err(err) => state._unserializeable_state.error = Some(format!("Save failed: {}", err)),
+        }
+    }
+    if let Some(path) = open_path {
+        match load_patch(state, &path) {
+            Ok(_) => state._unserializeable_state.error = None,
+            Err(err) => state._unserializeable_state.error = Some(format!("Open failed: {}", err)),
+        }
+    }
+}
+
+pub fn preset_browser(ctx: &egui::Context, state: &mut SoundNodeGraphState) {
+    if !state._unserializeable_state.preset_browser.open {
+        return;
+    }
+    if !state._unserializeable_state.preset_browser.scanned {
+        state._unserializeable_state.preset_browser.refresh();
+    }
+
+    let mut save = false;
+    let mut load = None;
+    let browser = &mut state._unserializeable_state.preset_browser;
+    let metadata = &mut state.user_state.patch_metadata;
+    egui::SidePanel::left("preset_browser")
+        .resizable(true)
+        .default_width(220.0)
+        .show(ctx, |ui| {
+            ui.heading("Presets");
+            egui::Grid::new("preset_metadata")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("name");
+                    ui.text_edit_singleline(&mut metadata.name);
+                    ui.end_row();
+                    ui.label("author");
+                    ui.text_edit_singleline(&mut metadata.author);
+                    ui.end_row();
+                    ui.label("tags");
+                    if ui.text_edit_singleline(&mut browser.tags_input).changed() {
+                        metadata.tags = browser
+                            .tags_input
+                            .split(',')
+                            .map(|x| x.trim().to_string())
+                            .filter(|x| !x.is_empty())
+                            .collect();
+                    }
+                    ui.end_row();
+                });
+            if ui.button("Save to presets").clicked() {
+                save = true;
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("search");
+                ui.text_edit_singleline(&mut browser.filter);
+                if ui
+                    .button("⟳")
+                    .on_hover_text("Rescan preset folder")
+                    .clicked()
+                {
+                    browser.refresh();
+                }
+            });
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if browser.entries.is_empty() {
+                    ui.label("No presets found.");
+                }
+                for entry in browser
+                    .entries
+                    .iter()
+                    .filter(|x| x.matches(&browser.filter))
+                {
+                    ui.group(|ui| {
+                        if ui
+                            .selectable_label(
+                                *metadata == entry.metadata,
+                                egui::RichText::new(&entry.metadata.name).strong(),
+                            )
+                            .on_hover_text(entry.path.to_string_lossy())
+                            .clicked()
+                        {
+                            load = Some(entry.path.clone());
+                        }
+                        if !entry.metadata.author.is_empty() {
+                            ui.small(format!("by {}", entry.metadata.author));
+                        }
+                        if !entry.metadata.tags.is_empty() {
+                            ui.small(entry.metadata.tags.join(", "));
+                        }
+                    });
+                }
+            });
+        });
+
+    if save {
+        let file_name = preset_file_name(&state.user_state.patch_metadata);
+        let result = match preset_folder() {
+            Some(folder) => match fs::create_dir_all(&folder) {
+                Ok(_) => save_patch(state, &folder.join(file_name)),
+                Err(err) => Err(err.into()),
+            },
+            None => Err("No preset folder available".into()),
+        };
+        match result {
+            Ok(_) => state._unserializeable_state.error = None,
+            Err(err) => state._unserializeable_state.error = Some(format!("Save failed: {}", err)),
+        }
+        state._unserializeable_state.preset_browser.refresh();
+    }
+    if let Some(path) = load {
+        match load_patch(state, &path) {
+            Ok(_) => state._unserializeable_state.error = None,
+            Err(err) => state._unserializeable_state.error = Some(format!("Open failed: {}", err)),
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use super::audition;
+use super::compiler::detached_state;
+use super::graph::{NodeId, SoundNodeGraph, SoundNodeGraphState};
+use crate::sound_map::DawSource;
+use eframe::egui;
+use egui_plot::{Line, Plot, PlotBounds, PlotPoints};
+use realfft::{RealFftPlanner, RealToComplex};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+const TAP_SIZE: usize = 4096;
+const FFT_SIZE: usize = 2048;
+const WAVEFORM_BUCKETS: usize = 256;
+
+/// A lock-free ring buffer the audio thread writes its output into, so the editor can draw it
+/// without the audio thread ever waiting on a lock. Only one thread should push at a time.
+pub struct ScopeRing {
+    samples: Box<[AtomicU32]>,
+    position: AtomicUsize,
+}
+
+impl ScopeRing {
+    fn new(size: usize) -> Self {
+        Self {
+            samples: (0..size).map(|_| AtomicU32::new(0)).collect(),
+            position: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn push(&self, sample: f32) {
+        let position = self.position.load(Ordering::Relaxed);
+        self.samples[position % self.samples.len()].store(sample.to_bits(), Ordering::Relaxed);
+        self.position
+            .store(position.wrapping_add(1), Ordering::Release);
+    }
+
+    pub fn position(&self) -> usize {
+        self.position.load(Ordering::Acquire)
+    }
+
+    /// The most recent samples, oldest first.
+    pub fn read(&self) -> Vec<f32> {
+        let position = self.position();
+        let len = self.samples.len();
+        (0..len)
+            .map(|i| f32::from_bits(self.samples[(position + i) % len].load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct ScopeTap(pub Arc<ScopeRing>);
+
+impl Default for ScopeTap {
+    fn default() -> Self {
+        Self(Arc::new(ScopeRing::new(TAP_SIZE)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ScopeWindow {
+    #[default]
+    Short,
+    Medium,
+    Long,
+}
+
+impl ScopeWindow {
+    pub const ALL: [ScopeWindow; 3] = [ScopeWindow::Short, ScopeWindow::Medium, ScopeWindow::Long];
+
+    fn seconds(&self) -> f32 {
+        match self {
+            ScopeWindow::Short => 0.02,
+            ScopeWindow::Medium => 0.2,
+            ScopeWindow::Long => 2.0,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ScopeWindow::Short => "20 ms",
+            ScopeWindow::Medium => "200 ms",
+            ScopeWindow::Long => "2 s",
+        }
+    }
+}
+
+/// The waveform and spectrum points of a preview.
+type PreviewPlot = (Vec<[f64; 2]>, Vec<[f64; 2]>);
+/// A rendered preview with the graph revision and window it was rendered for.
+type PreviewResult = (NodeId, usize, ScopeWindow, Result<PreviewPlot, String>);
+
+#[derive(Default)]
+pub struct ScopePreview {
+    pub window: ScopeWindow,
+    pub spectrum: bool,
+    waveform: Vec<[f64; 2]>,
+    bins: Vec<[f64; 2]>,
+    error: Option<String>,
+    revision: Option<usize>,
+    rendered_window: ScopeWindow,
+    /// The revision and window of the render the worker is busy with.
+    pending: Option<(usize, ScopeWindow)>,
+}
+
+impl ScopePreview {
+    fn set_plot(&mut self, result: Result<PreviewPlot, String>) {
+        match result {
+            Ok((waveform, bins)) => {
+                self.waveform = waveform;
+                self.bins = bins;
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+}
+
+/// A copy of the graph and the previews to render from it.
+struct PreviewJob {
+    graph: SoundNodeGraph,
+    revision: usize,
+    previews: Vec<(NodeId, ScopeWindow)>,
+    ctx: egui::Context,
+}
+
+/// The thread offline previews are rendered on, so evaluating and rendering them never holds up
+/// the editor. Jobs queued behind a newer one are skipped, the newest always covers every preview
+/// that is out of date.
+struct PreviewWorker {
+    jobs: Sender<PreviewJob>,
+    results: Receiver<PreviewResult>,
+}
+
+impl PreviewWorker {
+    fn spawn(fft: Arc<dyn RealToComplex<f32>>) -> Self {
+        let (jobs, job_receiver) = channel::<PreviewJob>();
+        let (result_sender, results) = channel();
+        std::thread::spawn(move || {
+            while let Ok(mut job) = job_receiver.recv() {
+                while let Ok(newer) = job_receiver.try_recv() {
+                    job = newer;
+                }
+                let state = &mut job.graph.state;
+                let sample_rate = state._unserializeable_state.queue.get_sample_rate();
+                for (node_id, window) in job.previews {
+                    let result = render_node(state, node_id, window)
+                        .map(|samples| {
+                            let len = (window.seconds() * sample_rate) as usize;
+                            (
+                                waveform(&samples[..len.min(samples.len())]),
+                                spectrum(&*fft, &samples, sample_rate),
+                            )
+                        })
+                        .map_err(|x| x.to_string());
+                    if result_sender
+                        .send((node_id, job.revision, window, result))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+                job.ctx.request_repaint();
+            }
+        });
+        Self { jobs, results }
+    }
+}
+
+/// The scope previews opened on nodes, plus the tap the audio thread writes the patch output to.
+/// Only the patch output is tapped live, every other node shows an offline render, which is
+/// redone whenever the graph changes.
+pub struct Scopes {
+    pub previews: HashMap<NodeId, ScopePreview>,
+    pub tap: ScopeTap,
+    live_position: usize,
+    live: bool,
+    worker: Option<PreviewWorker>,
+    fft: Arc<dyn RealToComplex<f32>>,
+}
+
+impl Default for Scopes {
+    fn default() -> Self {
+        Self {
+            previews: HashMap::new(),
+            tap: ScopeTap::default(),
+            live_position: 0,
+            live: false,
+            worker: None,
+            fft: RealFftPlanner::<f32>::new().plan_fft_forward(FFT_SIZE),
+        }
+    }
+}
+
+impl Scopes {
+    pub fn is_live(&self) -> bool {
+        self.live
+    }
+
+    pub fn toggle(&mut self, node_id: NodeId) {
+        if self.previews.remove(&node_id).is_none() {
+            self.previews.insert(node_id, ScopePreview::default());
+        }
+    }
+}
+
+/// Min/max pairs per bucket, so long windows of audio rate signals still show their envelope.
+fn waveform(samples: &[f32]) -> Vec<[f64; 2]> {
+    let bucket = (samples.len() / WAVEFORM_BUCKETS).max(1);
+    let mut points = vec![];
+    for (idx, chunk) in samples.chunks(bucket).enumerate() {
+        let x = (idx * bucket) as f64 / samples.len().max(1) as f64;
+        let min = chunk.iter().cloned().fold(f32::MAX, f32::min);
+        let max = chunk.iter().cloned().fold(f32::MIN, f32::max);
+        points.push([x, min as f64]);
+        if max != min {
+            points.push([x, max as f64]);
+        }
+    }
+    points
+}
+
+/// Magnitude in dB against log10 of frequency, from a Hann windowed FFT of the first samples.
+fn spectrum(fft: &dyn RealToComplex<f32>, samples: &[f32], sample_rate: f32) -> Vec<[f64; 2]> {
+    let mut input = vec![0.0; FFT_SIZE];
+    for (idx, sample) in samples.iter().take(FFT_SIZE).enumerate() {
+        let hann = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * idx as f32 / FFT_SIZE as f32).cos();
+        input[idx] = sample * hann;
+    }
+    let mut output = fft.make_output_vec();
+    if fft.process(&mut input, &mut output).is_err() {
+        return vec![];
+    }
+    output
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(idx, x)| {
+            let freq = idx as f32 * sample_rate / FFT_SIZE as f32;
+            let db = 20.0 * (x.norm() * 2.0 / FFT_SIZE as f32).max(1e-6).log10();
+            [(freq.log10()) as f64, db as f64]
+        })
+        .collect()
+}
+
+fn render_node(
+    state: &mut SoundNodeGraphState,
+    node_id: NodeId,
+    window: ScopeWindow,
+) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+    let mut sound = audition::render_node_source(state, node_id)?;
+    let len = (window.seconds() * state._unserializeable_state.queue.get_sample_rate()) as usize;
+    Ok((0..len.max(FFT_SIZE))
+        .map(|i| sound.next(i as f32, 0).unwrap_or_default())
+        .collect())
+}
+
+/// Sends every open preview that is out of date with the graph to the worker to be rendered
+/// offline, and feeds the patch output node's preview from the audio thread tap while audio is
+/// playing.
+pub fn update_previews(ctx: &egui::Context, state: &mut SoundNodeGraphState) {
+    let scopes = &mut state.user_state.scopes;
+    if scopes.previews.is_empty() {
+        return;
+    }
+    let position = scopes.tap.0.position();
+    scopes.live = position != scopes.live_position;
+    scopes.live_position = position;
+    let sample_rate = state._unserializeable_state.queue.get_sample_rate();
+    let live_node = match state.group_stack.is_empty() {
+        true => state.user_state.vst_output_node_id,
+        false => None,
+    };
+    if scopes.live {
+        if let Some(preview) = live_node.and_then(|x| scopes.previews.get_mut(&x)) {
+            let samples = scopes.tap.0.read();
+            preview.set_plot(Ok((
+                waveform(&samples),
+                spectrum(&*scopes.fft, &samples, sample_rate),
+            )));
+            preview.revision = None;
+            preview.pending = None;
+        }
+        ctx.request_repaint();
+    }
+
+    if let Some(worker) = &scopes.worker {
+        while let Ok((node_id, revision, window, result)) = worker.results.try_recv() {
+            if let Some(preview) = scopes.previews.get_mut(&node_id) {
+                if preview.pending == Some((revision, window)) {
+                    preview.set_plot(result);
+                    preview.revision = Some(revision);
+                    preview.rendered_window = window;
+                    preview.pending = None;
+                }
+            }
+        }
+    }
+
+    let graph = &state.editor_state.graph;
+    let scopes = &mut state.user_state.scopes;
+    scopes
+        .previews
+        .retain(|node_id, _| graph.nodes.contains_key(*node_id));
+    let revision = state.user_state.history.revision();
+    let live = scopes.live;
+    let stale = scopes
+        .previews
+        .iter()
+        .filter(|(node_id, _)| !(live && live_node == Some(**node_id)))
+        .filter(|(_, x)| x.revision != Some(revision) || x.rendered_window != x.window)
+        .map(|(node_id, x)| (*node_id, x.window))
+        .collect::<Vec<_>>();
+    let requested = |x: &ScopePreview| x.pending == Some((revision, x.window));
+    if stale
+        .iter()
+        .all(|(node_id, _)| scopes.previews.get(node_id).is_some_and(requested))
+    {
+        return;
+    }
+
+    let mut job = detached_state(
+        state,
+        sample_rate,
+        state._unserializeable_state.queue.get_bpm(),
+    );
+    // The previews are of nodes in the graph on screen, which is a group's when one is open.
+    job.state.editor_state.graph = state.editor_state.graph.clone();
+    let scopes = &mut state.user_state.scopes;
+    for (node_id, window) in stale.iter() {
+        if let Some(preview) = scopes.previews.get_mut(node_id) {
+            preview.pending = Some((revision, *window));
+        }
+    }
+    let job = PreviewJob {
+        graph: job,
+        revision,
+        previews: stale,
+        ctx: ctx.clone(),
+    };
+    let fft = scopes.fft.clone();
+    let worker = scopes
+        .worker
+        .get_or_insert_with(|| PreviewWorker::spawn(fft.clone()));
+    if let Err(err) = worker.jobs.send(job) {
+        // The thread panicked in a render, so start a new one.
+        let worker = scopes.worker.insert(PreviewWorker::spawn(fft));
+        let _ = worker.jobs.send(err.0);
+    }
+}
+
+pub fn scope_ui(ui: &mut egui::Ui, node_id: NodeId, preview: &mut ScopePreview, live: bool) {
+    ui.horizontal(|ui| {
+        for window in ScopeWindow::ALL {
+            ui.selectable_value(&mut preview.window, window, window.name());
+        }
+        ui.separator();
+        ui.selectable_value(&mut preview.spectrum, false, "wave");
+        ui.selectable_value(&mut preview.spectrum, true, "spectrum");
+        if live {
+            ui.colored_label(egui::Color32::GOLD, "live");
+        }
+    });
+    if let Some(error) = &preview.error {
+        ui.colored_label(ui.visuals().error_fg_color, error);
+        return;
+    }
+    let (points, bounds) = match preview.spectrum {
+        true => (
+            preview.bins.clone(),
+            PlotBounds::from_min_max([1.3, -90.0], [4.35, 6.0]),
+        ),
+        false => (
+            preview.waveform.clone(),
+            PlotBounds::from_min_max([0.0, -1.1], [1.0, 1.1]),
+        ),
+    };
+    Plot::new(format!("scope_{:?}", node_id))
+        .height(80.0)
+        .width(200.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .allow_double_click_reset(false)
+        .show_axes(false)
+        .show(ui, |plot_ui| {
+            plot_ui.set_plot_bounds(bounds);
+            plot_ui.line(Line::new(PlotPoints::from(points)));
+        });
+}
@@ -0,0 +1,187 @@
+use super::graph::NodeId;
+use super::note::{Note, NoteSpeed, Octave};
+use crate::sounds::tracker::{TrackerEffect, TrackerNote};
+use eframe::egui::{self, ComboBox, DragValue};
+
+enum GridEdit {
+    AddStep(usize),
+    RemoveStep(usize),
+    AddLane,
+    RemoveLane,
+}
+
+fn step_ui(ui: &mut egui::Ui, id: &str, tracker_note: &mut TrackerNote) {
+    ComboBox::from_id_salt(format!("{}_note", id))
+        .selected_text(match &tracker_note.note {
+            Some(note) => note.to_string(),
+            None => "~".to_string(),
+        })
+        .width(36.0)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut tracker_note.note, None, "~");
+            for note_variant in Note::ALL {
+                ui.selectable_value(
+                    &mut tracker_note.note,
+                    Some(note_variant.clone()),
+                    note_variant.to_string(),
+                );
+            }
+        });
+    ComboBox::from_id_salt(format!("{}_octave", id))
+        .selected_text(tracker_note.octave.to_string())
+        .width(24.0)
+        .show_ui(ui, |ui| {
+            for octave in Octave::ALL {
+                ui.selectable_value(&mut tracker_note.octave, octave.clone(), octave.to_string());
+            }
+        });
+    ComboBox::from_id_salt(format!("{}_speed", id))
+        .selected_text(tracker_note.speed.to_string())
+        .width(72.0)
+        .show_ui(ui, |ui| {
+            for speed_variant in NoteSpeed::ALL {
+                ui.selectable_value(
+                    &mut tracker_note.speed,
+                    speed_variant.clone(),
+                    speed_variant.to_string(),
+                );
+            }
+        });
+    ui.add(
+        DragValue::new(&mut tracker_note.velocity)
+            .range(0.0..=1.0)
+            .speed(0.01)
+            .max_decimals(2),
+    )
+    .on_hover_text("velocity");
+    ui.horizontal(|ui| {
+        ComboBox::from_id_salt(format!("{}_effect", id))
+            .selected_text(tracker_note.effect.name())
+            .width(48.0)
+            .show_ui(ui, |ui| {
+                for effect in TrackerEffect::ALL {
+                    if ui
+                        .selectable_label(
+                            tracker_note.effect.name() == effect.name(),
+                            effect.name(),
+                        )
+                        .clicked()
+                        && tracker_note.effect.name() != effect.name()
+                    {
+                        tracker_note.effect = effect;
+                    }
+                }
+            });
+        match &mut tracker_note.effect {
+            TrackerEffect::Retrigger(times) => {
+                ui.add(DragValue::new(times).range(1..=16).suffix("x"));
+            }
+            TrackerEffect::Cut(cut) => {
+                ui.add(
+                    DragValue::new(cut)
+                        .range(0.0..=1.0)
+                        .speed(0.01)
+                        .max_decimals(2),
+                );
+            }
+            TrackerEffect::Arpeggio(first, second) => {
+                ui.add(DragValue::new(first).range(0..=24));
+                ui.add(DragValue::new(second).range(0..=24));
+            }
+            TrackerEffect::None | TrackerEffect::Slide => {}
+        }
+    });
+    ui.checkbox(&mut tracker_note.tie, "")
+        .on_hover_text("tie to the previous step");
+}
+
+/// The pattern of a tracker node, one column of steps per lane.
+pub fn tracker_grid(ui: &mut egui::Ui, node_id: NodeId, notes: &mut Vec<TrackerNote>) {
+    let lane_count = notes.iter().map(|x| x.lane + 1).max().unwrap_or(1);
+    let steps = (0..lane_count)
+        .map(|lane| {
+            (0..notes.len())
+                .filter(|idx| notes[*idx].lane == lane)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let rows = steps.iter().map(|x| x.len()).max().unwrap_or(0);
+    let mut edits = vec![];
+    egui::ScrollArea::both()
+        .id_salt(format!("tracker_{:?}", node_id))
+        .max_height(240.0)
+        .max_width(640.0)
+        .show(ui, |ui| {
+            egui::Grid::new(format!("tracker_grid_{:?}", node_id))
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("");
+                    for lane in 0..lane_count {
+                        ui.label(format!("lane {}", lane + 1));
+                        for label in ["oct", "len", "vel", "fx", "tie"] {
+                            ui.label(label);
+                        }
+                    }
+                    ui.end_row();
+                    for row in 0..rows {
+                        ui.label(format!("{:02}", row));
+                        for (lane, lane_steps) in steps.iter().enumerate() {
+                            match lane_steps.get(row) {
+                                Some(idx) => step_ui(
+                                    ui,
+                                    &format!("tracker_{:?}_{}_{}", node_id, lane, row),
+                                    &mut notes[*idx],
+                                ),
+                                None => {
+                                    for _ in 0..6 {
+                                        ui.label("");
+                                    }
+                                }
+                            }
+                        }
+                        ui.end_row();
+                    }
+                    ui.label("");
+                    for (lane, lane_steps) in steps.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if lane_steps.len() > 1 && ui.button("−").clicked() {
+                                edits.push(GridEdit::RemoveStep(lane));
+                            }
+                            if ui.button("+").clicked() {
+                                edits.push(GridEdit::AddStep(lane));
+                            }
+                        });
+                        for _ in 0..5 {
+                            ui.label("");
+                        }
+                    }
+                    ui.end_row();
+                });
+        });
+    ui.horizontal(|ui| {
+        if ui.button("+ lane").clicked() {
+            edits.push(GridEdit::AddLane);
+        }
+        if lane_count > 1 && ui.button("− lane").clicked() {
+            edits.push(GridEdit::RemoveLane);
+        }
+    });
+    for edit in edits {
+        match edit {
+            GridEdit::AddStep(lane) => notes.push(TrackerNote {
+                lane,
+                ..TrackerNote::default()
+            }),
+            GridEdit::AddLane => notes.push(TrackerNote {
+                lane: lane_count,
+                ..TrackerNote::default()
+            }),
+            GridEdit::RemoveStep(lane) => {
+                if let Some(idx) = steps[lane].last() {
+                    notes.remove(*idx);
+                }
+            }
+            GridEdit::RemoveLane => notes.retain(|x| x.lane + 1 < lane_count),
+        }
+    }
+}
@@ -0,0 +1,511 @@
+use super::graph::{FileManager, SoundNodeGraphState};
+use super::note::Pitch;
+use crate::sound_map::AtomicF32;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::path::Path;
+
+pub const MIDI_NOTES: usize = 128;
+const CENTS_PER_OCTAVE: f64 = 1200.0;
+
+/// Which kind of Scala file a tuning dialog is for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TuningFile {
+    Scale,
+    Mapping,
+}
+
+/// Lines of a Scala file that aren't comments.
+fn scala_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().filter(|x| !x.starts_with('!'))
+}
+
+fn first_token(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or("")
+}
+
+/// A scale from a Scala `.scl` file, as the cents of each degree above the first. The last
+/// degree is the period the scale repeats at, usually an octave.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScalaScale {
+    pub description: String,
+    pub degrees: Vec<f64>,
+}
+
+impl ScalaScale {
+    pub fn equal_temperament() -> Self {
+        Self {
+            description: "12 tone equal temperament".to_string(),
+            degrees: (1..=12).map(|x| x as f64 * 100.0).collect(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = scala_lines(text);
+        let description = lines
+            .next()
+            .ok_or("missing description")?
+            .trim()
+            .to_string();
+        let count = first_token(lines.next().ok_or("missing note count")?).parse::<usize>()?;
+        let mut degrees = Vec::with_capacity(count);
+        for line in lines.take(count) {
+            let token = first_token(line);
+            let cents = if token.contains('.') {
+                token.parse::<f64>()?
+            } else {
+                let (numerator, denominator) = token.split_once('/').unwrap_or((token, "1"));
+                let ratio = numerator.parse::<f64>()? / denominator.parse::<f64>()?;
+                if ratio <= 0.0 {
+                    return Err(format!("bad ratio {}", token).into());
+                }
+                ratio.log2() * CENTS_PER_OCTAVE
+            };
+            degrees.push(cents);
+        }
+        if degrees.len() != count || count == 0 {
+            return Err("the scale has fewer notes than it says".into());
+        }
+        Ok(Self {
+            description,
+            degrees,
+        })
+    }
+
+    fn period(&self) -> f64 {
+        self.degrees[self.degrees.len() - 1]
+    }
+
+    /// The cents of a degree above the first, carrying on into the periods above and below.
+    fn cents(&self, degree: i32) -> f64 {
+        let len = self.degrees.len() as i32;
+        let idx = degree.rem_euclid(len);
+        let step = match idx {
+            0 => 0.0,
+            x => self.degrees[x as usize - 1],
+        };
+        degree.div_euclid(len) as f64 * self.period() + step
+    }
+}
+
+/// A Scala `.kbm` keyboard mapping, which places a scale's degrees on the midi keys and tunes
+/// one of them to a frequency.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyboardMapping {
+    pub first_note: i32,
+    pub last_note: i32,
+    /// The key the scale's first degree is on.
+    pub middle_note: i32,
+    pub reference_note: i32,
+    pub reference_frequency: f64,
+    /// The degree the mapping repeats at, zero for the scale's period.
+    pub octave_degree: i32,
+    /// The degree of each key in one repeat of the mapping, none for keys left silent. Empty
+    /// maps every key to the next degree.
+    pub keys: Vec<Option<i32>>,
+}
+
+impl Default for KeyboardMapping {
+    fn default() -> Self {
+        Self {
+            first_note: 0,
+            last_note: MIDI_NOTES as i32 - 1,
+            middle_note: 60,
+            reference_note: 69,
+            reference_frequency: 440.0,
+            octave_degree: 0,
+            keys: vec![],
+        }
+    }
+}
+
+impl KeyboardMapping {
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut lines = scala_lines(text).map(first_token);
+        let mut next = |name: &str| lines.next().ok_or(format!("missing {}", name));
+        let size = next("map size")?.parse::<usize>()?;
+        let first_note = next("first note")?.parse::<i32>()?;
+        let last_note = next("last note")?.parse::<i32>()?;
+        let middle_note = next("middle note")?.parse::<i32>()?;
+        let reference_note = next("reference note")?.parse::<i32>()?;
+        let reference_frequency = next("reference frequency")?.parse::<f64>()?;
+        let octave_degree = next("octave degree")?.parse::<i32>()?;
+        let mut keys = Vec::with_capacity(size);
+        for _ in 0..size {
+            // Scala allows leaving the end of the mapping out, those keys are silent.
+            keys.push(match lines.next() {
+                Some("x") | None => None,
+                Some(x) => Some(x.parse::<i32>()?),
+            });
+        }
+        if reference_frequency <= 0.0 {
+            return Err("the reference frequency has to be above zero".into());
+        }
+        Ok(Self {
+            first_note,
+            last_note,
+            middle_note,
+            reference_note,
+            reference_frequency,
+            octave_degree,
+            keys,
+        })
+    }
+
+    /// The cents of a key above the middle note, none if the mapping leaves it silent.
+    fn cents(&self, scale: &ScalaScale, note: i32) -> Option<f64> {
+        let offset = note - self.middle_note;
+        if self.keys.is_empty() {
+            return Some(scale.cents(offset));
+        }
+        let len = self.keys.len() as i32;
+        let degree = self.keys[offset.rem_euclid(len) as usize]?;
+        let period = match self.octave_degree {
+            0 => scale.period(),
+            x => scale.cents(x),
+        };
+        Some(offset.div_euclid(len) as f64 * period + scale.cents(degree))
+    }
+}
+
+/// The patch's tuning. Without a scale it's 12 tone equal temperament, and without a mapping
+/// the scale starts on middle C with A4 at 440 hz.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Tuning {
+    pub scale: Option<ScalaScale>,
+    pub mapping: Option<KeyboardMapping>,
+    #[serde(skip)]
+    pub open: bool,
+}
+
+impl Tuning {
+    /// The frequency of a midi key, none for keys the mapping leaves silent.
+    pub fn frequency(&self, note: i32) -> Option<f32> {
+        if self.scale.is_none() && self.mapping.is_none() {
+            return Some((440.0 * 2.0_f64.powf((note - 69) as f64 / 12.0)) as f32);
+        }
+        let scale = self
+            .scale
+            .clone()
+            .unwrap_or_else(ScalaScale::equal_temperament);
+        let mapping = self.mapping.clone().unwrap_or_default();
+        if note < mapping.first_note || note > mapping.last_note {
+            return None;
+        }
+        let cents = mapping.cents(&scale, note)?;
+        let reference = mapping
+            .cents(&scale, mapping.reference_note)
+            .unwrap_or_else(|| scale.cents(mapping.reference_note - mapping.middle_note));
+        Some(
+            (mapping.reference_frequency * 2.0_f64.powf((cents - reference) / CENTS_PER_OCTAVE))
+                as f32,
+        )
+    }
+
+    pub fn pitch_frequency(&self, pitch: &Pitch) -> Option<f32> {
+        self.frequency(pitch.midi_note())
+    }
+
+    /// The frequency of every midi key, zero for silent ones.
+    pub fn frequencies(&self) -> Vec<f32> {
+        (0..MIDI_NOTES as i32)
+            .map(|x| self.frequency(x).unwrap_or(0.0))
+            .collect()
+    }
+
+    pub fn name(&self) -> String {
+        match &self.scale {
+            Some(x) if !x.description.is_empty() => x.description.clone(),
+            Some(x) => format!("{} note scale", x.degrees.len()),
+            None => "12 tone equal temperament".to_string(),
+        }
+    }
+}
+
+/// The frequency of each midi key under the patch's tuning, zero for silent keys. Kept up to
+/// date by the compiler, so the audio thread can read it without locking the graph.
+#[derive(Debug)]
+pub struct TuningTable([AtomicF32; MIDI_NOTES]);
+
+impl Default for TuningTable {
+    fn default() -> Self {
+        let table = Self([0; MIDI_NOTES].map(|_| AtomicF32::new(0.0)));
+        table.store(&Tuning::default());
+        table
+    }
+}
+
+impl TuningTable {
+    pub fn store(&self, tuning: &Tuning) {
+        for (slot, frequency) in self.0.iter().zip(tuning.frequencies()) {
+            slot.store(frequency);
+        }
+    }
+
+    #[inline]
+    pub fn frequency(&self, note: u8) -> Option<f32> {
+        let frequency = self.0.get(note as usize)?.load();
+        (frequency > 0.0).then_some(frequency)
+    }
+}
+
+impl FileManager {
+    /// Asks for the Scala file that was requested from the tuning window.
+    pub fn open_tuning_dialog(&mut self) {
+        if let Some(kind) = self.tuning_active {
+            let (name, extension) = match kind {
+                TuningFile::Scale => ("scala scale", "scl"),
+                TuningFile::Mapping => ("scala keyboard mapping", "kbm"),
+            };
+            self.tuning_path = rfd::FileDialog::new()
+                .add_filter(name, &[extension])
+                .pick_file()
+                .map(|x| (x, kind));
+            self.tuning_active = None;
+        }
+    }
+}
+
+fn load_tuning_file(
+    tuning: &mut Tuning,
+    path: &Path,
+    kind: TuningFile,
+) -> Result<(), Box<dyn Error>> {
+    let text = std::fs::read_to_string(path)?;
+    match kind {
+        TuningFile::Scale => tuning.scale = Some(ScalaScale::parse(&text)?),
+        TuningFile::Mapping => tuning.mapping = Some(KeyboardMapping::parse(&text)?),
+    }
+    Ok(())
+}
+
+fn tuning_changed(state: &mut SoundNodeGraphState) {
+    state.user_state.is_saved = false;
+    state
+        ._unserializeable_state
+        .tuning
+        .0
+        .store(&state.user_state.tuning);
+    state._unserializeable_state.compiler.invalidate();
+}
+
+/// Loads the Scala file picked in the tuning dialog once it has closed.
+pub fn handle_tuning_files(state: &mut SoundNodeGraphState) {
+    let path = match state.user_state.files.try_lock() {
+        Ok(mut files) => {
+            if files.tuning_active.is_some() {
+                return;
+            }
+            files.tuning_path.take()
+        }
+        Err(_x) => return,
+    };
+    if let Some((path, kind)) = path {
+        match load_tuning_file(&mut state.user_state.tuning, &path, kind) {
+            Ok(_) => {
+                state._unserializeable_state.error = None;
+                tuning_changed(state);
+            }
+            Err(err) => {
+                state._unserializeable_state.error = Some(format!("Loading tuning failed: {}", err))
+            }
+        }
+    }
+}
+
+/// The window for loading the patch's Scala scale and keyboard mapping.
+pub fn tuning_window(ctx: &egui::Context, state: &mut SoundNodeGraphState) {
+    let mut open = state.user_state.tuning.open;
+    if !open {
+        return;
+    }
+    let mut changed = false;
+    let mut request = None;
+    egui::Window::new("Tuning").open(&mut open).show(ctx, |ui| {
+        let tuning = &mut state.user_state.tuning;
+        egui::Grid::new("tuning").show(ui, |ui| {
+            ui.label("scale");
+            ui.label(tuning.name());
+            if ui.button("Load .scl…").clicked() {
+                request = Some(TuningFile::Scale);
+            }
+            if tuning.scale.is_some() && ui.button("✖").clicked() {
+                tuning.scale = None;
+                changed = true;
+            }
+            ui.end_row();
+            ui.label("mapping");
+            match &tuning.mapping {
+                Some(x) => ui.label(format!(
+                    "key {} at {:.2} hz, scale from key {}",
+                    x.reference_note, x.reference_frequency, x.middle_note
+                )),
+                None => ui.label("A4 at 440 hz, scale from middle C"),
+            };
+            if ui.button("Load .kbm…").clicked() {
+                request = Some(TuningFile::Mapping);
+            }
+            if tuning.mapping.is_some() && ui.button("✖").clicked() {
+                tuning.mapping = None;
+                changed = true;
+            }
+            ui.end_row();
+        });
+    });
+    if let Some(kind) = request {
+        if let Ok(mut files) = state.user_state.files.lock() {
+            files.tuning_active = Some(kind);
+        }
+    }
+    state.user_state.tuning.open = open;
+    if changed {
+        tuning_changed(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JUST_MAJOR: &str = "! just.scl
+!
+Just major
+ 7
+!
+ 9/8
+ 5/4
+ 4/3
+ 3/2
+ 5/3
+ 15/8
+ 2/1
+";
+
+    /// The white keys of a 12 key mapping hold the seven degrees, the black keys are silent.
+    const WHITE_KEYS: &str = "! white.kbm
+12
+0
+127
+60
+69
+440.0
+7
+! mapping
+0
+x
+1
+x
+2
+3
+x
+4
+x
+5
+x
+6
+";
+
+    fn close(a: Option<f32>, b: f32) -> bool {
+        a.is_some_and(|a| (a - b).abs() < 0.01)
+    }
+
+    fn scala_tuning(scale: &str, mapping: &str) -> Tuning {
+        Tuning {
+            scale: Some(ScalaScale::parse(scale).unwrap()),
+            mapping: Some(KeyboardMapping::parse(mapping).unwrap()),
+            open: false,
+        }
+    }
+
+    #[test]
+    fn parses_ratios_and_cents() {
+        let scale = ScalaScale::parse(JUST_MAJOR).unwrap();
+        assert_eq!(scale.description, "Just major");
+        assert_eq!(scale.degrees.len(), 7);
+        assert!((scale.degrees[0] - 203.91).abs() < 0.01);
+        assert!((scale.degrees[6] - 1200.0).abs() < 1e-9);
+
+        // Lines with a dot are cents, a whole number on its own is a ratio over one.
+        let scale = ScalaScale::parse("cents\n3\n100.0\n250.5 ! quarter\n2\n").unwrap();
+        assert_eq!(scale.degrees, vec![100.0, 250.5, 1200.0]);
+    }
+
+    #[test]
+    fn rejects_bad_scales() {
+        assert!(ScalaScale::parse("short\n3\n100.0\n200.0\n").is_err());
+        assert!(ScalaScale::parse("empty\n0\n").is_err());
+        assert!(ScalaScale::parse("negative\n1\n-3/2\n").is_err());
+        assert!(ScalaScale::parse("! only comments\n").is_err());
+    }
+
+    #[test]
+    fn equal_temperament_by_default() {
+        let tuning = Tuning::default();
+        assert!(close(tuning.frequency(69), 440.0));
+        assert!(close(tuning.frequency(60), 261.63));
+        assert!(close(tuning.frequency(57), 220.0));
+        // Without a mapping a scale starts on middle C with A4 at 440 hz.
+        let tuning = Tuning {
+            scale: Some(ScalaScale::equal_temperament()),
+            ..Tuning::default()
+        };
+        assert!(close(tuning.frequency(69), 440.0));
+        assert!(close(tuning.frequency(81), 880.0));
+    }
+
+    #[test]
+    fn maps_degrees_onto_keys() {
+        let mapping = KeyboardMapping::parse(WHITE_KEYS).unwrap();
+        assert_eq!(mapping.keys.len(), 12);
+        assert_eq!(mapping.keys[1], None);
+        assert_eq!(mapping.keys[11], Some(6));
+        let tuning = scala_tuning(JUST_MAJOR, WHITE_KEYS);
+        assert!(close(tuning.frequency(69), 440.0));
+        // A is the sixth degree, 5/3 above C.
+        assert!(close(tuning.frequency(60), 264.0));
+        assert!(close(tuning.frequency(62), 297.0));
+        assert!(close(tuning.frequency(72), 528.0));
+        assert!(close(tuning.frequency(48), 132.0));
+        assert_eq!(tuning.frequency(61), None);
+        assert_eq!(tuning.frequencies()[61], 0.0);
+    }
+
+    #[test]
+    fn mapping_edge_cases() {
+        // A map size of zero puts every key on the next degree.
+        let tuning = Tuning {
+            scale: Some(ScalaScale::equal_temperament()),
+            mapping: Some(KeyboardMapping::parse("0\n0\n127\n60\n69\n440.0\n0\n").unwrap()),
+            open: false,
+        };
+        assert!(close(tuning.frequency(57), 220.0));
+        assert!(close(tuning.frequency(61), 277.18));
+
+        // Keys left off the end of the mapping are silent.
+        let mapping = KeyboardMapping::parse("12\n0\n127\n60\n69\n440.0\n0\n0\n1\n2\n").unwrap();
+        assert_eq!(mapping.keys[2], Some(2));
+        assert_eq!(mapping.keys[3], None);
+
+        // The reference key can be outside the playable range.
+        let tuning = Tuning {
+            mapping: Some(KeyboardMapping::parse("0\n64\n127\n60\n60\n261.63\n0\n").unwrap()),
+            ..Tuning::default()
+        };
+        assert_eq!(tuning.frequency(60), None);
+        assert!(close(tuning.frequency(72), 523.26));
+
+        // A silent reference key is tuned as if the scale carried on through it.
+        let silent_reference = WHITE_KEYS.replace("\n69\n", "\n61\n");
+        let tuning = scala_tuning(JUST_MAJOR, &silent_reference);
+        assert_eq!(tuning.frequency(61), None);
+        assert!(close(tuning.frequency(62), 440.0));
+    }
+
+    #[test]
+    fn rejects_bad_mappings() {
+        assert!(KeyboardMapping::parse("12\n0\n127\n60\n69\n").is_err());
+        assert!(KeyboardMapping::parse("0\n0\n127\n60\n69\n0.0\n0\n").is_err());
+        assert!(KeyboardMapping::parse("1\n0\n127\n60\n69\n440.0\n0\ny\n").is_err());
+    }
+}
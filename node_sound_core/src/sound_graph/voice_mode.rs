@@ -0,0 +1,205 @@
+use eframe::egui::{self, ComboBox, DragValue};
+use serde::{Deserialize, Serialize};
+
+const MIDI_NOTES: usize = 128;
+pub const MAX_GLIDE_MS: f32 = 2000.0;
+pub const MAX_POLYPHONY: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub enum VoiceMode {
+    #[default]
+    Poly,
+    /// One voice, every new note starts its envelope again.
+    Mono,
+    /// One voice, notes played while another is held carry on its envelope.
+    Legato,
+}
+
+impl VoiceMode {
+    pub const ALL: [VoiceMode; 3] = [VoiceMode::Poly, VoiceMode::Mono, VoiceMode::Legato];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VoiceMode::Poly => "poly",
+            VoiceMode::Mono => "mono",
+            VoiceMode::Legato => "legato",
+        }
+    }
+}
+
+/// Which of the held keys the mono voice plays.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub enum NotePriority {
+    #[default]
+    Last,
+    Low,
+    High,
+}
+
+impl NotePriority {
+    pub const ALL: [NotePriority; 3] = [NotePriority::Last, NotePriority::Low, NotePriority::High];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NotePriority::Last => "last",
+            NotePriority::Low => "low",
+            NotePriority::High => "high",
+        }
+    }
+}
+
+/// Which voice gives way when a note is played with every voice in use.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Default)]
+pub enum StealPolicy {
+    #[default]
+    Oldest,
+    Quietest,
+    /// A voice already playing the same key, otherwise the oldest.
+    SameNote,
+    /// A voice whose key has been let go, otherwise the oldest.
+    ReleaseFirst,
+}
+
+impl StealPolicy {
+    pub const ALL: [StealPolicy; 4] = [
+        StealPolicy::Oldest,
+        StealPolicy::Quietest,
+        StealPolicy::SameNote,
+        StealPolicy::ReleaseFirst,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StealPolicy::Oldest => "oldest",
+            StealPolicy::Quietest => "quietest",
+            StealPolicy::SameNote => "same note",
+            StealPolicy::ReleaseFirst => "released",
+        }
+    }
+}
+
+/// How the synth hands notes to its voices. The plugin exposes these as host parameters, and
+/// they are saved with the patch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceSettings {
+    pub mode: VoiceMode,
+    pub priority: NotePriority,
+    /// How long the pitch takes to slide to a new note in the mono modes, zero to jump.
+    pub glide_ms: f32,
+    /// How many voices can sound at once in poly mode, up to `MAX_POLYPHONY`.
+    pub polyphony: usize,
+    pub stealing: StealPolicy,
+    /// Stops starting new voices while rendering takes too much of each block's time.
+    pub cpu_guard: bool,
+    /// Set when a patch is loaded, so the plugin knows to copy these into its parameters.
+    #[serde(skip)]
+    pub loaded: bool,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            mode: VoiceMode::Poly,
+            priority: NotePriority::Last,
+            glide_ms: 0.0,
+            polyphony: 16,
+            stealing: StealPolicy::Oldest,
+            cpu_guard: false,
+            loaded: false,
+        }
+    }
+}
+
+impl VoiceSettings {
+    /// Shows the settings, returns whether any changed.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let before = self.clone();
+        egui::Grid::new("voice_settings").show(ui, |ui| {
+            ui.label("mode");
+            ComboBox::from_id_salt("voice_mode")
+                .selected_text(self.mode.name())
+                .show_ui(ui, |ui| {
+                    for mode in VoiceMode::ALL {
+                        ui.selectable_value(&mut self.mode, mode, mode.name());
+                    }
+                });
+            ui.end_row();
+            ui.label("priority");
+            ComboBox::from_id_salt("note_priority")
+                .selected_text(self.priority.name())
+                .show_ui(ui, |ui| {
+                    for priority in NotePriority::ALL {
+                        ui.selectable_value(&mut self.priority, priority, priority.name());
+                    }
+                });
+            ui.end_row();
+            ui.label("glide");
+            ui.add(
+                DragValue::new(&mut self.glide_ms)
+                    .range(0.0..=MAX_GLIDE_MS)
+                    .suffix(" ms"),
+            );
+            ui.end_row();
+            ui.label("polyphony");
+            ui.add(DragValue::new(&mut self.polyphony).range(1..=MAX_POLYPHONY));
+            ui.end_row();
+            ui.label("stealing");
+            ComboBox::from_id_salt("steal_policy")
+                .selected_text(self.stealing.name())
+                .show_ui(ui, |ui| {
+                    for stealing in StealPolicy::ALL {
+                        ui.selectable_value(&mut self.stealing, stealing, stealing.name());
+                    }
+                });
+            ui.end_row();
+            ui.label("cpu guard");
+            ui.checkbox(&mut self.cpu_guard, "").on_hover_text(
+                "Hold back new voices while the synth is close to using up its time",
+            );
+            ui.end_row();
+        });
+        *self != before
+    }
+}
+
+/// The keys being held down, for picking the note a mono voice plays. Preallocated, so it can
+/// run on the audio thread.
+pub struct HeldNotes {
+    /// Held notes as `(channel, note, velocity)`, in the order they were played.
+    held: Vec<(u8, u8, f32)>,
+}
+
+impl Default for HeldNotes {
+    fn default() -> Self {
+        Self {
+            held: Vec::with_capacity(MIDI_NOTES),
+        }
+    }
+}
+
+impl HeldNotes {
+    pub fn note_on(&mut self, channel: u8, note: u8, velocity: f32) {
+        self.held.retain(|x| x.1 != note);
+        if self.held.len() < MIDI_NOTES {
+            self.held.push((channel, note, velocity));
+        }
+    }
+
+    pub fn note_off(&mut self, note: u8) {
+        self.held.retain(|x| x.1 != note);
+    }
+
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+
+    /// The held note that should sound, as `(channel, note, velocity)`.
+    pub fn current(&self, priority: NotePriority) -> Option<(u8, u8, f32)> {
+        match priority {
+            NotePriority::Last => self.held.last().copied(),
+            NotePriority::Low => self.held.iter().min_by_key(|x| x.1).copied(),
+            NotePriority::High => self.held.iter().max_by_key(|x| x.1).copied(),
+        }
+    }
+}
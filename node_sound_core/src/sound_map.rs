@@ -7,7 +7,10 @@ use std::{
     fmt::Debug,
     io::ErrorKind,
     rc::Rc,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
 };
 
 pub trait DawSource: DynClone + Debug {
@@ -93,11 +96,32 @@ impl DawSource for RefSource {
     }
 }
 
+/// An f32 shared with the audio thread, stored as its bit pattern so neither side ever waits on a
+/// lock to read or write it.
+#[derive(Debug, Default)]
+pub struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    pub fn new(value: f32) -> Self {
+        Self(AtomicU32::new(value.to_bits()))
+    }
+
+    #[inline]
+    pub fn load(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    #[inline]
+    pub fn store(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed)
+    }
+}
+
 pub struct SoundQueue {
     queue: Vec<GenericSource>,
     sample_rate: f32,
     speed: f32,
-    bpm: Arc<Mutex<f32>>,
+    bpm: Arc<AtomicF32>,
 }
 
 impl Default for SoundQueue {
@@ -112,7 +136,7 @@ impl SoundQueue {
             queue: vec![],
             speed: 1.0,
             sample_rate: sample_rate,
-            bpm: Arc::new(Mutex::new(120.0)),
+            bpm: Arc::new(AtomicF32::new(120.0)),
         };
         queue.push_sound(Box::new(ConstWave::new(0.0)));
         return queue;
@@ -170,10 +194,10 @@ impl SoundQueue {
         self.speed
     }
 
-    pub fn get_bpm(&self) -> Arc<Mutex<f32>> {
+    pub fn get_bpm(&self) -> Arc<AtomicF32> {
         self.bpm.clone()
     }
-    pub fn set_bpm(&mut self, bpm: Arc<Mutex<f32>>) {
+    pub fn set_bpm(&mut self, bpm: Arc<AtomicF32>) {
         self.bpm = bpm
     }
 }
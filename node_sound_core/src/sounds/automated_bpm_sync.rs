@@ -1,6 +1,10 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-use crate::{sound_graph::note::NoteSpeedType, sound_map::DawSource};
+use crate::{
+    sound_graph::note::NoteSpeedType,
+    sound_map::{AtomicF32, DawSource},
+    sounds::transport::{HostTransport, sync_phase},
+};
 
 #[derive(Clone, Debug)]
 pub struct AutomatedBPMSync<S: DawSource> {
@@ -9,18 +13,20 @@ pub struct AutomatedBPMSync<S: DawSource> {
     speed: f32,
     note_speed_type: NoteSpeedType,
     note_speed: S,
-    bpm: Arc<Mutex<f32>>,
+    bpm: Arc<AtomicF32>,
+    host: Option<Arc<HostTransport>>,
 }
 
 impl<S: DawSource> AutomatedBPMSync<S> {
     #[inline]
     pub fn new(
         sample_rate: f32,
-        bpm: Arc<Mutex<f32>>,
+        bpm: Arc<AtomicF32>,
         note_speed: S,
         note_speed_type: NoteSpeedType,
         table: Vec<f32>,
         speed: f32,
+        host: Option<Arc<HostTransport>>,
     ) -> Self {
         Self {
             sample_rate,
@@ -29,25 +35,30 @@ impl<S: DawSource> AutomatedBPMSync<S> {
             note_speed_type,
             table,
             speed,
+            host,
         }
     }
 }
 
 impl<S: DawSource + Clone> DawSource for AutomatedBPMSync<S> {
-    fn next(&mut self, mut index: f32, channel: u8) -> Option<f32> {
-        let seconds_per_note = self
+    fn next(&mut self, index: f32, channel: u8) -> Option<f32> {
+        let note_beats = self
             .note_speed_type
             .get_beats_type()
             .iter()
             .nth(self.note_speed.next(index, channel).unwrap_or_default() as usize)
             .cloned()
             .unwrap_or_default()
-            .get_beats()
-            / (self.bpm.lock().map(|x| *x).unwrap_or(120.0) / 60.0);
+            .get_beats();
+        let seconds_per_note = note_beats / (self.bpm.load() / 60.0);
         let samples_per_note = seconds_per_note * self.sample_rate;
-        index /= self.speed;
-        index %= samples_per_note;
-        index /= samples_per_note;
+        let index = sync_phase(
+            self.host.as_ref(),
+            index,
+            self.speed,
+            note_beats,
+            samples_per_note,
+        );
         let real_idx = index * self.table.len() as f32;
         let idx = real_idx.floor() as usize;
         let initial_weight = real_idx - idx as f32;
@@ -1,6 +1,10 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-use crate::{sound_graph::note::NoteSpeed, sound_map::DawSource};
+use crate::{
+    sound_graph::note::NoteSpeed,
+    sound_map::{AtomicF32, DawSource},
+    sounds::transport::{HostTransport, sync_phase},
+};
 
 #[derive(Clone, Debug)]
 pub struct BPMSync<I: DawSource> {
@@ -9,7 +13,8 @@ pub struct BPMSync<I: DawSource> {
     table: Vec<f32>,
     speed: f32,
     note_speed: NoteSpeed,
-    bpm: Arc<Mutex<f32>>,
+    bpm: Arc<AtomicF32>,
+    host: Option<Arc<HostTransport>>,
 }
 
 impl<I: DawSource + Clone> BPMSync<I> {
@@ -17,10 +22,11 @@ impl<I: DawSource + Clone> BPMSync<I> {
     pub fn new(
         source: I,
         sample_rate: f32,
-        bpm: Arc<Mutex<f32>>,
+        bpm: Arc<AtomicF32>,
         note_speed: NoteSpeed,
         table: Vec<f32>,
         speed: f32,
+        host: Option<Arc<HostTransport>>,
     ) -> Self {
         Self {
             source,
@@ -29,19 +35,23 @@ impl<I: DawSource + Clone> BPMSync<I> {
             note_speed,
             table,
             speed,
+            host,
         }
     }
 }
 
 impl<I: DawSource + Clone> DawSource for BPMSync<I> {
-    fn next(&mut self, mut index: f32, channel: u8) -> Option<f32> {
+    fn next(&mut self, index: f32, channel: u8) -> Option<f32> {
         self.source.next(index, channel).map(|x| {
-            let seconds_per_note =
-                self.note_speed.get_beats() / (self.bpm.lock().map(|x| *x).unwrap_or(120.0) / 60.0);
+            let seconds_per_note = self.note_speed.get_beats() / (self.bpm.load() / 60.0);
             let samples_per_note = seconds_per_note * self.sample_rate;
-            index /= self.speed;
-            index %= samples_per_note;
-            index /= samples_per_note;
+            let index = sync_phase(
+                self.host.as_ref(),
+                index,
+                self.speed,
+                self.note_speed.get_beats(),
+                samples_per_note,
+            );
             let real_idx = index * self.table.len() as f32;
             let idx = real_idx.floor() as usize;
             let initial_weight = real_idx - idx as f32;
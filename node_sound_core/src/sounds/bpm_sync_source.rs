@@ -1,6 +1,10 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-use crate::{sound_graph::note::NoteSpeed, sound_map::DawSource};
+use crate::{
+    sound_graph::note::NoteSpeed,
+    sound_map::{AtomicF32, DawSource},
+    sounds::transport::{HostTransport, sync_phase},
+};
 
 #[derive(Clone, Debug)]
 pub struct BPMSyncSource {
@@ -8,17 +12,19 @@ pub struct BPMSyncSource {
     table: Vec<f32>,
     speed: f32,
     note_speed: NoteSpeed,
-    bpm: Arc<Mutex<f32>>,
+    bpm: Arc<AtomicF32>,
+    host: Option<Arc<HostTransport>>,
 }
 
 impl BPMSyncSource {
     #[inline]
     pub fn new(
         sample_rate: f32,
-        bpm: Arc<Mutex<f32>>,
+        bpm: Arc<AtomicF32>,
         note_speed: NoteSpeed,
         table: Vec<f32>,
         speed: f32,
+        host: Option<Arc<HostTransport>>,
     ) -> Self {
         Self {
             sample_rate,
@@ -26,18 +32,22 @@ impl BPMSyncSource {
             note_speed,
             table,
             speed,
+            host,
         }
     }
 }
 
 impl DawSource for BPMSyncSource {
-    fn next(&mut self, mut index: f32, _channel: u8) -> Option<f32> {
-        let seconds_per_note =
-            self.note_speed.get_beats() / (self.bpm.lock().map(|x| *x).unwrap_or(120.0) / 60.0);
+    fn next(&mut self, index: f32, _channel: u8) -> Option<f32> {
+        let seconds_per_note = self.note_speed.get_beats() / (self.bpm.load() / 60.0);
         let samples_per_note = seconds_per_note * self.sample_rate;
-        index /= self.speed;
-        index %= samples_per_note;
-        index /= samples_per_note;
+        let index = sync_phase(
+            self.host.as_ref(),
+            index,
+            self.speed,
+            self.note_speed.get_beats(),
+            samples_per_note,
+        );
         let real_idx = index * self.table.len() as f32;
         let idx = real_idx.floor() as usize;
         let initial_weight = real_idx - idx as f32;
@@ -1,30 +1,39 @@
-use crate::{sound_graph::note::Pitch, sound_map::DawSource};
+use crate::{sound_graph::tuning::Tuning, sound_map::DawSource};
 use std::f32;
 
 #[derive(Clone, Debug)]
 pub struct ClampToNote<I: DawSource> {
     source: I,
+    /// The frequency of every key the tuning plays.
+    pitches: Vec<f32>,
 }
 
 impl<I: DawSource> ClampToNote<I> {
     #[inline]
-    pub fn new(source: I) -> Self {
-        Self { source }
+    pub fn new(source: I, tuning: &Tuning) -> Self {
+        Self {
+            source,
+            pitches: tuning
+                .frequencies()
+                .into_iter()
+                .filter(|x| *x > 0.0)
+                .collect(),
+        }
     }
 }
 
 impl<I: DawSource + Clone> DawSource for ClampToNote<I> {
     fn next(&mut self, index: f32, channel: u8) -> Option<f32> {
         let val = self.source.next(index, channel).unwrap_or_default();
-        let mut least_idx = 0;
+        let mut nearest = val;
         let mut least = f32::MAX;
-        for (idx, pitch) in Pitch::ALL_FREQ.iter().enumerate() {
+        for pitch in self.pitches.iter() {
             let pitch_diff = (pitch - val).abs();
             if pitch_diff < least {
-                least_idx = idx;
+                nearest = *pitch;
                 least = pitch_diff;
             }
         }
-        Some(Pitch::ALL[least_idx].match_freq())
+        Some(nearest)
     }
 }
@@ -1,3 +1,4 @@
+use crate::sound_map::AtomicF32;
 use crate::sound_map::DawSource;
 use crate::sound_map::GenericSource;
 use crate::sounds::const_wave::ConstWave;
@@ -6,7 +7,6 @@ use crate::sounds::lfo::Lfo;
 use crate::sounds::minus::Minus;
 use crate::sounds::mix::Mix;
 use std::sync::Arc;
-use std::sync::Mutex;
 
 #[derive(Clone, Debug)]
 pub struct DawAutomationMix {
@@ -16,7 +16,7 @@ pub struct DawAutomationMix {
 impl DawAutomationMix {
     #[inline]
     pub fn new<S: DawSource + Clone + 'static, S2: DawSource + Clone + 'static>(
-        channel: Arc<Mutex<f32>>,
+        channel: Arc<AtomicF32>,
         audio1: S,
         audio2: S2,
     ) -> Self {
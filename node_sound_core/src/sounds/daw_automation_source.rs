@@ -1,24 +1,20 @@
-use crate::sound_map::DawSource;
+use crate::sound_map::{AtomicF32, DawSource};
 use std::sync::Arc;
-use std::sync::Mutex;
 
 #[derive(Clone, Debug)]
 pub struct DawAutomationChannel {
-    channel: Arc<Mutex<f32>>,
+    channel: Arc<AtomicF32>,
 }
 
 impl DawAutomationChannel {
     #[inline]
-    pub fn new(channel: Arc<Mutex<f32>>) -> Self {
+    pub fn new(channel: Arc<AtomicF32>) -> Self {
         Self { channel }
     }
 }
 
 impl DawSource for DawAutomationChannel {
     fn next(&mut self, _index: f32, _channel: u8) -> Option<f32> {
-        match self.channel.lock() {
-            Err(_x) => None,
-            Ok(x) => Some(*x),
-        }
+        Some(self.channel.load())
     }
 }
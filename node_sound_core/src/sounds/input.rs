@@ -1,24 +1,121 @@
-use crate::sound_map::DawSource;
-use std::sync::Arc;
-use std::sync::Mutex;
+use crate::sound_map::{AtomicF32, DawSource};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// The input buses of the plugins, the main input first then the sidechain.
+pub const INPUT_BUSES: [&str; 2] = ["main", "sidechain"];
+pub const SIDECHAIN_BUS: usize = 1;
+pub const INPUT_CHANNELS: [&str; 3] = ["stereo", "left", "right"];
+/// How many samples of input are kept, a little over 5 seconds at 48kHz.
+pub const INPUT_HISTORY: usize = 1 << 18;
+
+/// A lock-free history of the plugin's input, written by the audio thread one frame at a time.
+/// Sources read it relative to `now`, the index the plugin is rendering the graph at, so nodes
+/// that move the index around, like delays, read the input from that many samples ago.
+pub struct InputHistory {
+    /// A few megabytes, so it's only made once a plugin calls `allocate`. Graph states built just
+    /// to be compiled never need it.
+    samples: OnceLock<[[Box<[AtomicF32]>; 2]; INPUT_BUSES.len()]>,
+    position: AtomicUsize,
+    now: AtomicF32,
+}
+
+impl Default for InputHistory {
+    fn default() -> Self {
+        Self {
+            samples: OnceLock::new(),
+            position: AtomicUsize::new(0),
+            now: AtomicF32::new(0.0),
+        }
+    }
+}
+
+impl std::fmt::Debug for InputHistory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputHistory")
+            .field("position", &self.position)
+            .finish()
+    }
+}
+
+impl InputHistory {
+    /// Makes the history's buffers. Until then it's silent and pushing does nothing, so call it
+    /// off the audio thread before the plugin starts processing.
+    pub fn allocate(&self) {
+        self.samples.get_or_init(|| {
+            std::array::from_fn(|_| {
+                std::array::from_fn(|_| (0..INPUT_HISTORY).map(|_| AtomicF32::new(0.0)).collect())
+            })
+        });
+    }
+
+    /// Adds the next left and right sample of every bus.
+    pub fn push(&self, frame: [[f32; 2]; INPUT_BUSES.len()]) {
+        let samples = match self.samples.get() {
+            Some(x) => x,
+            None => return,
+        };
+        let position = self.position.load(Ordering::Relaxed);
+        for (bus, channels) in samples.iter().zip(frame) {
+            for (samples, sample) in bus.iter().zip(channels) {
+                samples[position % INPUT_HISTORY].store(sample);
+            }
+        }
+        self.position
+            .store(position.wrapping_add(1), Ordering::Release);
+    }
+
+    /// Sets the index the graph is about to be asked for, which lines up with the newest frame.
+    #[inline]
+    pub fn set_now(&self, index: f32) {
+        self.now.store(index);
+    }
+
+    fn sample(&self, bus: usize, channel: usize, position: usize) -> f32 {
+        match self.samples.get() {
+            Some(x) => x[bus][channel][position % INPUT_HISTORY].load(),
+            None => 0.0,
+        }
+    }
+
+    /// The input at `index`, linearly interpolated between frames. Indexes after `now` read the
+    /// newest frame, ones older than the history read silence.
+    pub fn read(&self, bus: usize, channel: usize, index: f32) -> f32 {
+        let position = self.position.load(Ordering::Acquire);
+        let offset = (self.now.load() - index).max(0.0);
+        let back = offset.floor() as usize;
+        if back + 1 >= position.min(INPUT_HISTORY) {
+            return 0.0;
+        }
+        let newer = self.sample(bus, channel, position - 1 - back);
+        let older = self.sample(bus, channel, position - 2 - back);
+        newer + (older - newer) * offset.fract()
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct InputChannel {
-    channel: Arc<Mutex<(f32, f32)>>,
+    history: Arc<InputHistory>,
+    bus: usize,
+    channel: Option<u8>,
 }
 
 impl InputChannel {
+    /// `channel` is an index into `INPUT_CHANNELS`. Stereo follows the channel being asked for,
+    /// left and right play that input channel on both outputs.
     #[inline]
-    pub fn new(channel: Arc<Mutex<(f32, f32)>>) -> Self {
-        Self { channel }
+    pub fn new(history: Arc<InputHistory>, bus: usize, channel: usize) -> Self {
+        Self {
+            history,
+            bus: bus.min(INPUT_BUSES.len() - 1),
+            channel: channel.checked_sub(1).map(|x| x.min(1) as u8),
+        }
     }
 }
 
 impl DawSource for InputChannel {
-    fn next(&mut self, _index: f32, channel: u8) -> Option<f32> {
-        match self.channel.lock() {
-            Err(_x) => None,
-            Ok(x) => Some(if channel == 0 { x.0 } else { x.1 }),
-        }
+    fn next(&mut self, index: f32, channel: u8) -> Option<f32> {
+        let channel = self.channel.unwrap_or(channel).min(1);
+        Some(self.history.read(self.bus, channel as usize, index))
     }
 }
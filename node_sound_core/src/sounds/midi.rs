@@ -1,62 +1,322 @@
+use std::{error::Error, sync::Arc};
+
+use synthrs::midi::{EventType, MidiSong};
+
 use crate::{
     constants::MIDDLE_C_FREQ,
-    sound_map::DawSource,
-    sounds::wave_table::{WaveTableManager, WaveTableOscillator},
+    sound_graph::tuning::Tuning,
+    sound_map::{AtomicF32, DawSource},
 };
-use synthrs::{midi::MidiSong, synthesizer::make_samples_from_midi, wave};
 
+/// How many notes of a midi file can sound at once before the oldest is stolen.
+pub const MIDI_VOICES: usize = 16;
+/// Notes fade out over this long after they end, so they don't click.
+const RELEASE_SECONDS: f32 = 0.005;
+const MICROSECONDS_PER_MINUTE: f64 = 60_000_000.0;
+
+/// A note of a midi file, with its start and length in quarter notes.
+#[derive(Clone, Copy, Debug)]
+pub struct MidiNote {
+    pub start: f64,
+    pub length: f64,
+    pub note: u8,
+    pub velocity: f32,
+}
+
+/// Pairs up the note on and off events of every track, sorted by when they start.
+pub fn midi_notes(song: &MidiSong) -> Vec<MidiNote> {
+    let ticks_per_beat = song.time_unit.max(1) as f64;
+    let mut notes = vec![];
+    for track in song.tracks.iter() {
+        let mut open: Vec<(u8, u8, usize, f32)> = vec![];
+        for event in track.events.iter() {
+            let note = event.value1.min(127) as u8;
+            let velocity = event.value2 as f32 / 127.0;
+            let is_on = matches!(event.event_type, EventType::NoteOn) && velocity > 0.0;
+            let is_off = matches!(event.event_type, EventType::NoteOff)
+                || matches!(event.event_type, EventType::NoteOn) && velocity <= 0.0;
+            if is_off || is_on {
+                if let Some(idx) = open
+                    .iter()
+                    .position(|x| x.0 == event.channel && x.1 == note)
+                {
+                    let (_, _, start, velocity) = open.remove(idx);
+                    notes.push(MidiNote {
+                        start: start as f64 / ticks_per_beat,
+                        length: event.time.saturating_sub(start) as f64 / ticks_per_beat,
+                        note,
+                        velocity,
+                    });
+                }
+            }
+            if is_on {
+                open.push((event.channel, note, event.time, velocity));
+            }
+        }
+        // Notes that are never let go last until the end of the track.
+        for (_, note, start, velocity) in open {
+            notes.push(MidiNote {
+                start: start as f64 / ticks_per_beat,
+                length: (track.max_time.max(start) - start) as f64 / ticks_per_beat,
+                note,
+                velocity,
+            });
+        }
+    }
+    notes.sort_by(|a, b| a.start.total_cmp(&b.start));
+    notes
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_var_len(bytes: &[u8], at: &mut usize) -> Option<u32> {
+    let mut value = 0u32;
+    for _ in 0..4 {
+        let byte = *bytes.get(*at)?;
+        *at += 1;
+        value = (value << 7) | (byte & 0x7f) as u32;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// The tempo changes of a standard midi file as `(tick, bpm)`, which the parsed song doesn't keep.
+pub fn read_tempo_changes(path: &str) -> Result<Vec<(u64, f64)>, Box<dyn Error>> {
+    let bytes = std::fs::read(path)?;
+    if bytes.get(0..4) != Some(b"MThd") {
+        return Err("not a midi file".into());
+    }
+    let mut changes = vec![];
+    let mut at = 8 + read_u32(&bytes, 4).ok_or("truncated header")? as usize;
+    while let Some(length) = read_u32(&bytes, at + 4) {
+        let end = (at + 8 + length as usize).min(bytes.len());
+        if bytes.get(at..at + 4) == Some(b"MTrk") {
+            let mut cursor = at + 8;
+            let mut tick = 0u64;
+            let mut status = 0u8;
+            while cursor < end {
+                tick += read_var_len(&bytes, &mut cursor).ok_or("bad delta time")? as u64;
+                let mut byte = *bytes.get(cursor).ok_or("truncated track")?;
+                if byte & 0x80 != 0 {
+                    cursor += 1;
+                } else {
+                    // Running status reuses the last status byte.
+                    byte = status;
+                }
+                match byte {
+                    0xff => {
+                        let kind = *bytes.get(cursor).ok_or("truncated meta event")?;
+                        cursor += 1;
+                        let length = read_var_len(&bytes, &mut cursor).ok_or("bad meta event")?;
+                        let data = bytes
+                            .get(cursor..cursor + length as usize)
+                            .ok_or("truncated meta event")?;
+                        if kind == 0x51 && data.len() == 3 {
+                            let micros = u32::from_be_bytes([0, data[0], data[1], data[2]]);
+                            if micros > 0 {
+                                changes.push((tick, MICROSECONDS_PER_MINUTE / micros as f64));
+                            }
+                        }
+                        cursor += length as usize;
+                    }
+                    0xf0 | 0xf7 => {
+                        let length = read_var_len(&bytes, &mut cursor).ok_or("bad sysex event")?;
+                        cursor += length as usize;
+                    }
+                    0xc0..=0xdf => {
+                        status = byte;
+                        cursor += 1;
+                    }
+                    0x80..=0xef => {
+                        status = byte;
+                        cursor += 2;
+                    }
+                    _ => return Err("bad midi event".into()),
+                }
+            }
+        }
+        at = end;
+    }
+    changes.sort_by_key(|x| x.0);
+    Ok(changes)
+}
+
+/// Where each tempo of a song starts, as `(beat, second, bpm)`.
 #[derive(Clone, Debug)]
-pub struct MidiRenderer {
-    wavetable: WaveTableOscillator,
+pub struct MidiTempoMap(Vec<(f64, f64, f64)>);
+
+impl MidiTempoMap {
+    /// Builds the map from `(tick, bpm)` changes, playing at `bpm` until the first one.
+    pub fn new(ticks_per_beat: f64, bpm: f64, changes: &[(u64, f64)]) -> Self {
+        let mut map = vec![(0.0, 0.0, bpm)];
+        for (tick, bpm) in changes.iter() {
+            let beat = *tick as f64 / ticks_per_beat.max(1.0);
+            let (last_beat, last_second, last_bpm) = map[map.len() - 1];
+            let second = last_second + (beat - last_beat) * 60.0 / last_bpm;
+            if beat <= last_beat {
+                map.pop();
+            }
+            map.push((beat, second, *bpm));
+        }
+        Self(map)
+    }
+
+    pub fn beats_at(&self, seconds: f64) -> f64 {
+        let idx = self.0.partition_point(|x| x.1 <= seconds).max(1) - 1;
+        let (beat, second, bpm) = self.0[idx];
+        beat + (seconds - second) * bpm / 60.0
+    }
 }
 
-impl MidiRenderer {
-    #[inline]
-    pub fn new<S: DawSource>(
+#[derive(Clone, Debug)]
+struct MidiVoice {
+    /// Which copy of the source in the player's pool the note plays through.
+    slot: usize,
+    end: f64,
+    start_index: f32,
+    release_index: Option<f32>,
+    ratio: f32,
+    velocity: f32,
+}
+
+/// Plays the notes of a midi file through pitched copies of a source, each sounding note getting
+/// a copy of its own. The copies are made up front, so starting a note doesn't allocate.
+#[derive(Clone, Debug)]
+pub struct MidiPlayer<S: DawSource> {
+    pool: Vec<S>,
+    sample_rate: f32,
+    speed: f32,
+    notes: Vec<MidiNote>,
+    tempo: MidiTempoMap,
+    /// Plays the notes at the graph's tempo instead of the file's.
+    host_bpm: Option<Arc<AtomicF32>>,
+    /// The playback speed of each key relative to middle C, zero for keys left silent.
+    ratios: Vec<f32>,
+    voices: Vec<MidiVoice>,
+    next_note: usize,
+    beats: f64,
+    last_index: Option<f32>,
+}
+
+impl<S: DawSource + Clone> MidiPlayer<S> {
+    pub fn new(
         source: S,
-        song: MidiSong,
+        notes: Vec<MidiNote>,
+        tempo: MidiTempoMap,
         uses_speed: bool,
         speed: f32,
         sample_rate: f32,
-        cache: &mut WaveTableManager,
     ) -> Self {
         Self {
-            wavetable: cache.make_wavetable(
-                sample_rate,
-                MIDDLE_C_FREQ as f32,
-                source,
-                1.0,
-                MIDDLE_C_FREQ as f32,
-                uses_speed,
-                speed.clone(),
-                Box::new(|source, total_samples| {
-                    let mut source_samples: Vec<f64> = Vec::with_capacity(total_samples as usize);
-                    for i in 0..total_samples as usize {
-                        let sample = source.next(i as f32, 0).unwrap_or(0.0);
-                        source_samples.push(sample.into());
-                    }
-                    let sampler = |frequency: f64| {
-                        wave::sampler(
-                            frequency * speed as f64,
-                            &source_samples,
-                            source_samples.len(),
-                            MIDDLE_C_FREQ as f64,
-                            total_samples as usize,
-                        )
-                    };
-                    let midi_samples =
-                        make_samples_from_midi(sampler, total_samples as usize, true, song.clone())
-                            .expect("midi play failed");
-                    let left: Vec<_> = midi_samples.iter().map(|&x| x as f32).collect();
-                    (left.clone(), left)
-                }),
-            ),
-        }
-    }
-}
-
-impl DawSource for MidiRenderer {
+            pool: vec![source; MIDI_VOICES],
+            sample_rate,
+            speed: if uses_speed { speed } else { 1.0 },
+            notes,
+            tempo,
+            host_bpm: None,
+            ratios: vec![],
+            voices: Vec::with_capacity(MIDI_VOICES),
+            next_note: 0,
+            beats: 0.0,
+            last_index: None,
+        }
+        .tuning(&Tuning::default())
+    }
+
+    pub fn tuning(mut self, tuning: &Tuning) -> Self {
+        // Relative to the 12 tone middle C the graph is built at, so keys stay in tune with the
+        // rest of the patch even when the tuning leaves middle C unmapped.
+        self.ratios = tuning
+            .frequencies()
+            .into_iter()
+            .map(|x| x / MIDDLE_C_FREQ)
+            .collect();
+        self
+    }
+
+    pub fn host_bpm(mut self, bpm: Option<Arc<AtomicF32>>) -> Self {
+        self.host_bpm = bpm;
+        self
+    }
+
+    fn advance(&mut self, index: f32) {
+        let beats = match (&self.host_bpm, self.last_index) {
+            (Some(bpm), Some(last)) if index >= last => {
+                self.beats
+                    + (index - last) as f64 / self.sample_rate as f64 * bpm.load() as f64 / 60.0
+            }
+            (Some(bpm), _) => index as f64 / self.sample_rate as f64 * bpm.load() as f64 / 60.0,
+            (None, _) => self.tempo.beats_at(index as f64 / self.sample_rate as f64),
+        };
+        if beats < self.beats || self.last_index.is_none() {
+            // Jumped backwards, so start again from the notes at the new position.
+            self.voices.clear();
+            self.next_note = self.notes.partition_point(|x| x.start < beats);
+        }
+        self.beats = beats;
+        self.last_index = Some(index);
+        for voice in self.voices.iter_mut() {
+            if voice.release_index.is_none() && beats >= voice.end {
+                voice.release_index = Some(index);
+            }
+        }
+        let release = RELEASE_SECONDS * self.sample_rate;
+        self.voices
+            .retain(|x| x.release_index.is_none_or(|start| index - start < release));
+        while let Some(note) = self.notes.get(self.next_note).copied() {
+            if note.start > beats {
+                break;
+            }
+            self.next_note += 1;
+            let ratio = self.ratios[note.note as usize];
+            if ratio <= 0.0 {
+                continue;
+            }
+            let slot = if self.voices.len() >= MIDI_VOICES {
+                self.voices.remove(0).slot
+            } else {
+                match (0..MIDI_VOICES).find(|x| self.voices.iter().all(|voice| voice.slot != *x)) {
+                    Some(x) => x,
+                    None => continue,
+                }
+            };
+            self.voices.push(MidiVoice {
+                slot,
+                end: note.start + note.length,
+                start_index: index,
+                release_index: None,
+                ratio,
+                velocity: note.velocity,
+            });
+        }
+    }
+}
+
+impl<S: DawSource + Clone> DawSource for MidiPlayer<S> {
     fn next(&mut self, index: f32, channel: u8) -> Option<f32> {
-        self.wavetable.next(index, channel)
+        let index = index / self.speed;
+        if self.last_index != Some(index) {
+            self.advance(index);
+        }
+        if self.voices.is_empty() && self.next_note >= self.notes.len() {
+            return None;
+        }
+        let release = RELEASE_SECONDS * self.sample_rate;
+        let mut out = 0.0;
+        for voice in self.voices.iter() {
+            let gain = match voice.release_index {
+                Some(start) => 1.0 - (index - start) / release,
+                None => 1.0,
+            };
+            let position = (index - voice.start_index) * voice.ratio;
+            out += self.pool[voice.slot].next(position, channel).unwrap_or(0.0)
+                * voice.velocity
+                * gain;
+        }
+        Some(out)
     }
 }
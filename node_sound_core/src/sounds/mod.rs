@@ -42,6 +42,7 @@ pub mod mix;
 pub mod mod_raw;
 pub mod mod_source;
 pub mod noise;
+pub mod quantize_to_scale;
 pub mod random_duration;
 pub mod repeat_n;
 pub mod reverse;
@@ -52,9 +53,11 @@ pub mod skip;
 pub mod speed;
 pub mod split_channels;
 pub mod square;
+pub mod step_sequencer;
 pub mod switch;
 pub mod tracker;
 pub mod translate;
+pub mod transport;
 pub mod triangle;
 pub mod unison;
 pub mod vertical_wave_shaper;
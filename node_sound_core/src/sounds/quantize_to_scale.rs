@@ -0,0 +1,139 @@
+use crate::{
+    sound_graph::tuning::{MIDI_NOTES, Tuning},
+    sound_map::DawSource,
+};
+
+/// The scales the quantizer knows, as semitones above the root.
+pub const SCALES: [(&str, &[u8]); 11] = [
+    ("major", &[0, 2, 4, 5, 7, 9, 11]),
+    ("minor", &[0, 2, 3, 5, 7, 8, 10]),
+    ("harmonic minor", &[0, 2, 3, 5, 7, 8, 11]),
+    ("dorian", &[0, 2, 3, 5, 7, 9, 10]),
+    ("phrygian", &[0, 1, 3, 5, 7, 8, 10]),
+    ("lydian", &[0, 2, 4, 6, 7, 9, 11]),
+    ("mixolydian", &[0, 2, 4, 5, 7, 9, 10]),
+    ("locrian", &[0, 1, 3, 5, 6, 8, 10]),
+    ("major pentatonic", &[0, 2, 4, 7, 9]),
+    ("minor pentatonic", &[0, 3, 5, 7, 10]),
+    ("chromatic", &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+];
+/// Takes the intervals from the node's custom interval graph instead.
+pub const CUSTOM_SCALE: &str = "custom";
+pub const QUANTIZE_SIGNALS: [&str; 2] = ["frequency", "control"];
+
+/// Which semitones above the root are in a scale.
+pub fn scale_mask(intervals: &[u8]) -> [bool; 12] {
+    let mut mask = [false; 12];
+    for interval in intervals {
+        mask[*interval as usize % 12] = true;
+    }
+    mask
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuantizeSignal {
+    /// Values are frequencies in hz, snapped to the keys of the patch's tuning.
+    Frequency,
+    /// Values from -1 to 1 are spread over the octave range, like a pitch control voltage.
+    Control,
+}
+
+#[derive(Clone, Debug)]
+pub struct QuantizeToScale<S: DawSource> {
+    source: S,
+    signal: QuantizeSignal,
+    /// Where each note of the scale is, in semitones, sorted so it can be binary searched.
+    positions: Vec<f32>,
+    /// The value each note of the scale outputs.
+    values: Vec<f32>,
+    /// How many semitones closer a value has to be to another note than to the current one
+    /// before the output changes.
+    hysteresis: f32,
+    /// The number of semitones the -1 to 1 control range covers.
+    span: f32,
+    last: [Option<usize>; 2],
+}
+
+impl<S: DawSource> QuantizeToScale<S> {
+    /// `octaves` is the lowest and highest octave the notes are picked from, with middle C
+    /// starting octave 4.
+    pub fn new(
+        source: S,
+        signal: QuantizeSignal,
+        mask: [bool; 12],
+        root: usize,
+        octaves: (usize, usize),
+        hysteresis: f32,
+        tuning: &Tuning,
+    ) -> Self {
+        let (low, high) = (octaves.0.min(octaves.1), octaves.0.max(octaves.1));
+        let in_scale = |key: usize| mask[(key + 12 - root % 12) % 12];
+        let (positions, values): (Vec<f32>, Vec<f32>) = match signal {
+            QuantizeSignal::Frequency => {
+                let mut notes = ((low + 1) * 12..((high + 2) * 12).min(MIDI_NOTES))
+                    .filter(|x| in_scale(*x))
+                    .filter_map(|x| tuning.frequency(x as i32))
+                    .map(|x| (12.0 * x.log2(), x))
+                    .collect::<Vec<_>>();
+                notes.sort_by(|a, b| a.0.total_cmp(&b.0));
+                notes.into_iter().unzip()
+            }
+            QuantizeSignal::Control => {
+                let span = (high - low + 1) * 12;
+                (0..=span)
+                    .filter(|x| in_scale(*x))
+                    .map(|x| (x as f32, x as f32 / span as f32 * 2.0 - 1.0))
+                    .unzip()
+            }
+        };
+        Self {
+            source,
+            signal,
+            positions,
+            values,
+            hysteresis: hysteresis.max(0.0),
+            span: ((high - low + 1) * 12) as f32,
+            last: [None; 2],
+        }
+    }
+
+    fn position(&self, value: f32) -> f32 {
+        match self.signal {
+            QuantizeSignal::Frequency => 12.0 * value.max(f32::MIN_POSITIVE).log2(),
+            QuantizeSignal::Control => (value.clamp(-1.0, 1.0) + 1.0) * 0.5 * self.span,
+        }
+    }
+
+    fn nearest(&self, position: f32) -> usize {
+        let idx = self.positions.partition_point(|x| *x < position);
+        match idx {
+            0 => 0,
+            x if x >= self.positions.len() => self.positions.len() - 1,
+            x if position - self.positions[x - 1] <= self.positions[x] - position => x - 1,
+            x => x,
+        }
+    }
+}
+
+impl<S: DawSource> DawSource for QuantizeToScale<S> {
+    fn next(&mut self, index: f32, channel: u8) -> Option<f32> {
+        let value = self.source.next(index, channel)?;
+        if self.positions.is_empty() {
+            return Some(value);
+        }
+        let position = self.position(value);
+        let nearest = self.nearest(position);
+        let channel = (channel as usize).min(1);
+        let idx = match self.last[channel] {
+            Some(last)
+                if (position - self.positions[last]).abs()
+                    <= (position - self.positions[nearest]).abs() + self.hysteresis =>
+            {
+                last
+            }
+            _ => nearest,
+        };
+        self.last[channel] = Some(idx);
+        Some(self.values[idx])
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use crate::{
+    sound_graph::note::NoteSpeed,
+    sound_map::{AtomicF32, DawSource},
+    sounds::transport::HostTransport,
+};
+
+pub const MAX_RATCHETS: usize = 4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SequencerStep {
+    pub value: f32,
+    /// The chance the step's gate opens, 0 to 1.
+    pub probability: f32,
+    /// How many times the gate opens during the step.
+    pub ratchets: usize,
+}
+
+impl SequencerStep {
+    /// Splits drawn curves into `count` steps, averaging each one's part of the curve. The
+    /// probability and ratchet curves are mapped from -1 to 1 onto 0 to 1 and 1 to `MAX_RATCHETS`.
+    pub fn from_tables(
+        count: usize,
+        values: &[f32],
+        probabilities: &[f32],
+        ratchets: &[f32],
+    ) -> Vec<SequencerStep> {
+        let average = |table: &[f32], step: usize| {
+            let start = step * table.len() / count;
+            let end = ((step + 1) * table.len() / count)
+                .max(start + 1)
+                .min(table.len());
+            match start < end {
+                true => table[start..end].iter().sum::<f32>() / (end - start) as f32,
+                false => 0.0,
+            }
+        };
+        (0..count)
+            .map(|step| SequencerStep {
+                value: average(values, step),
+                probability: ((average(probabilities, step) + 1.0) * 0.5).clamp(0.0, 1.0),
+                ratchets: (1.0 + (average(ratchets, step) + 1.0) * 0.5 * (MAX_RATCHETS - 1) as f32)
+                    .round()
+                    .clamp(1.0, MAX_RATCHETS as f32) as usize,
+            })
+            .collect()
+    }
+}
+
+impl SequencerStep {
+    /// Whether the step's gate opens when it's the `count`th step played.
+    pub fn opens(&self, count: u64) -> bool {
+        chance(count) < self.probability
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SequencerOutput {
+    Value,
+    Gate,
+}
+
+/// A number from 0 to 1 that is the same every time it is asked for the same `n`, so the value
+/// and gate outputs agree on which steps were skipped.
+fn chance(n: u64) -> f32 {
+    let mut x = n.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 31;
+    (x >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[derive(Clone, Debug)]
+pub struct StepSequencer {
+    sample_rate: f32,
+    bpm: Arc<AtomicF32>,
+    note_speed: NoteSpeed,
+    speed: f32,
+    steps: Vec<SequencerStep>,
+    gate_length: f32,
+    output: SequencerOutput,
+    host: Option<Arc<HostTransport>>,
+}
+
+impl StepSequencer {
+    #[inline]
+    pub fn new(
+        sample_rate: f32,
+        bpm: Arc<AtomicF32>,
+        note_speed: NoteSpeed,
+        speed: f32,
+        steps: Vec<SequencerStep>,
+        gate_length: f32,
+        host: Option<Arc<HostTransport>>,
+    ) -> Self {
+        Self {
+            sample_rate,
+            bpm,
+            note_speed,
+            speed,
+            steps,
+            gate_length: gate_length.clamp(0.0, 1.0),
+            output: SequencerOutput::Value,
+            host,
+        }
+    }
+
+    /// The same sequence, but outputting its gate instead of its value.
+    pub fn gate(self) -> Self {
+        Self {
+            output: SequencerOutput::Gate,
+            ..self
+        }
+    }
+}
+
+impl DawSource for StepSequencer {
+    fn next(&mut self, index: f32, _channel: u8) -> Option<f32> {
+        if self.steps.is_empty() {
+            return Some(0.0);
+        }
+        let position = match self.host.as_ref().filter(|x| x.is_playing()) {
+            Some(host) => host.beats() / self.note_speed.get_beats(),
+            None => {
+                let samples_per_step =
+                    self.note_speed.get_beats() / (self.bpm.load() / 60.0) * self.sample_rate;
+                index / self.speed / samples_per_step
+            }
+        }
+        .max(0.0);
+        let count = position.floor() as u64;
+        let step = self.steps[(count % self.steps.len() as u64) as usize];
+        Some(match self.output {
+            SequencerOutput::Value => step.value,
+            SequencerOutput::Gate => {
+                let ratchet_phase = (position.fract() * step.ratchets as f32).fract();
+                match step.opens(count) && ratchet_phase < self.gate_length {
+                    true => 1.0,
+                    false => 0.0,
+                }
+            }
+        })
+    }
+}
@@ -1,108 +1,364 @@
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    sound_graph::note::{Note, NoteSpeed, Octave, Pitch},
-    sound_map::DawSource,
+    constants::MIDDLE_C_FREQ,
+    sound_graph::{
+        note::{Note, NoteSpeed, Octave, Pitch},
+        tuning::Tuning,
+    },
+    sound_map::{AtomicF32, DawSource},
+    sounds::transport::HostTransport,
 };
 
-#[derive(Clone, Debug)]
-pub struct Tracker<S: DawSource> {
-    sample_rate: f32,
-    source: S,
-    speed: f32,
-    notes: Vec<TrackerNote>,
-    bpm: Arc<Mutex<f32>>,
-    current_note: usize,
-    last_idx: f32,
-    table: Vec<f32>,
+/// How many times an arpeggio cycles through its three notes each step.
+const ARPEGGIO_CYCLES: f32 = 2.0;
+
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq)]
+pub enum TrackerEffect {
+    #[default]
+    None,
+    /// Glides from the previous step's pitch to this one over the step.
+    Slide,
+    /// Restarts the step's envelope this many times.
+    Retrigger(u8),
+    /// Silences the step after this fraction of it.
+    Cut(f32),
+    /// Cycles between the note and the note raised by each of these semitones.
+    Arpeggio(u8, u8),
+}
+
+impl TrackerEffect {
+    pub const ALL: [TrackerEffect; 5] = [
+        TrackerEffect::None,
+        TrackerEffect::Slide,
+        TrackerEffect::Retrigger(2),
+        TrackerEffect::Cut(0.5),
+        TrackerEffect::Arpeggio(4, 7),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrackerEffect::None => "-",
+            TrackerEffect::Slide => "slide",
+            TrackerEffect::Retrigger(_) => "retrig",
+            TrackerEffect::Cut(_) => "cut",
+            TrackerEffect::Arpeggio(_, _) => "arp",
+        }
+    }
 }
 
-#[derive(Clone, Deserialize, Serialize, Debug, Default)]
+#[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct TrackerNote {
     pub speed: NoteSpeed,
     pub note: Option<Note>, // option to signify silence.
+    #[serde(default = "TrackerNote::default_octave")]
+    pub octave: Octave,
+    #[serde(default = "TrackerNote::default_velocity")]
+    pub velocity: f32,
+    #[serde(default)]
+    pub effect: TrackerEffect,
+    /// Carries on from the previous step without restarting its envelope. A tied rest holds the
+    /// previous note.
+    #[serde(default)]
+    pub tie: bool,
+    #[serde(default)]
+    pub lane: usize,
+}
+
+impl Default for TrackerNote {
+    fn default() -> Self {
+        Self::new(NoteSpeed::default(), None)
+    }
 }
 
 impl TrackerNote {
     pub fn new(speed: NoteSpeed, note: Option<Note>) -> Self {
-        Self { speed, note }
+        Self {
+            speed,
+            note,
+            octave: Self::default_octave(),
+            velocity: Self::default_velocity(),
+            effect: TrackerEffect::None,
+            tie: false,
+            lane: 0,
+        }
+    }
+
+    fn default_octave() -> Octave {
+        Octave::O4
+    }
+
+    fn default_velocity() -> f32 {
+        1.0
+    }
+
+    pub fn midi_note(&self) -> Option<i32> {
+        let note = self.note.clone()?;
+        Some(Pitch(self.octave.clone(), note).midi_note())
+    }
+
+    /// The playback speed of the step's note raised by `semitones` keys under `tuning`, relative
+    /// to the 12 tone middle C the graph is built at. None for a rest or a key the tuning leaves
+    /// silent.
+    pub fn ratio(&self, tuning: &Tuning, semitones: i32) -> Option<f32> {
+        let key = self.midi_note()? + semitones;
+        Some(tuning.frequency(key)? / MIDDLE_C_FREQ)
+    }
+}
+
+/// The steps of each lane of a pattern, in order. Lanes without any steps are left out.
+pub fn tracker_lanes(notes: &[TrackerNote]) -> Vec<Vec<TrackerNote>> {
+    let count = notes.iter().map(|x| x.lane + 1).max().unwrap_or(0);
+    (0..count)
+        .map(|lane| {
+            notes
+                .iter()
+                .filter(|x| x.lane == lane)
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+struct TrackerLane<S: DawSource> {
+    source: S,
+    notes: Vec<TrackerNote>,
+    /// The pitch each step plays, with tied rests holding the note before them.
+    ratios: Vec<Option<f32>>,
+    /// The pitches of each step's arpeggio, the first being the step's own.
+    arpeggios: Vec<[Option<f32>; 3]>,
+    /// Where each step starts in its group of tied steps and how long the group is, in beats.
+    ties: Vec<(f32, f32)>,
+    current_note: usize,
+    last_idx: f32,
+    last_index: Option<f32>,
+    position: f32,
+}
+
+impl<S: DawSource> TrackerLane<S> {
+    fn new(source: S, notes: Vec<TrackerNote>) -> Self {
+        let mut ties = vec![(0.0, 0.0); notes.len()];
+        let mut group_start = 0;
+        for idx in 0..notes.len() {
+            if idx > 0 && !notes[idx].tie {
+                group_start = idx;
+            }
+            if notes.get(idx + 1).map(|x| x.tie) != Some(true) {
+                let mut offset = 0.0;
+                for group_idx in group_start..=idx {
+                    ties[group_idx].0 = offset;
+                    offset += notes[group_idx].speed.get_beats();
+                }
+                for group_idx in group_start..=idx {
+                    ties[group_idx].1 = offset;
+                }
+            }
+        }
+        let mut lane = Self {
+            source,
+            notes,
+            ratios: vec![],
+            arpeggios: vec![],
+            ties,
+            current_note: 0,
+            last_idx: -1.0,
+            last_index: None,
+            position: 0.0,
+        };
+        lane.tune(&Tuning::default());
+        lane
+    }
+
+    fn tune(&mut self, tuning: &Tuning) {
+        self.ratios.clear();
+        self.arpeggios.clear();
+        for (idx, tracker_note) in self.notes.iter().enumerate() {
+            let held = match tracker_note.tie {
+                true => idx.checked_sub(1).and_then(|x| self.ratios[x]),
+                false => None,
+            };
+            let ratio = tracker_note.ratio(tuning, 0).or(held);
+            self.ratios.push(ratio);
+            self.arpeggios.push(match tracker_note.effect {
+                TrackerEffect::Arpeggio(first, second) => [
+                    ratio,
+                    tracker_note.ratio(tuning, first as i32),
+                    tracker_note.ratio(tuning, second as i32),
+                ],
+                _ => [ratio; 3],
+            });
+        }
+    }
+
+    /// The step the host's song position is on and how far through it, 0 to 1. The pattern
+    /// repeats from the start of the song.
+    fn host_position(&self, host: &HostTransport) -> (usize, f32) {
+        let total = self.notes.iter().map(|x| x.speed.get_beats()).sum::<f32>();
+        let mut beats = host.beats().rem_euclid(total);
+        for (idx, tracker_note) in self.notes.iter().enumerate() {
+            let length = tracker_note.speed.get_beats();
+            if beats < length {
+                return (idx, (beats / length).min(0.9999));
+            }
+            beats -= length;
+        }
+        (self.notes.len() - 1, 0.9999)
+    }
+
+    /// How far through the current step the lane is, 0 to 1, moving on to the next step when
+    /// the last one has finished.
+    fn step(&mut self, index: f32, speed: f32, samples_per_note: f32) -> f32 {
+        let index = ((index / speed) % samples_per_note) / samples_per_note;
+        if self.last_idx > index {
+            self.current_note += 1;
+            if self.current_note >= self.notes.len() {
+                self.current_note = 0;
+            }
+        }
+        self.last_idx = index;
+        index
+    }
+
+    fn ratio(&self, phase: f32) -> Option<f32> {
+        let ratio = self.ratios[self.current_note]?;
+        Some(match self.notes[self.current_note].effect {
+            TrackerEffect::Slide => {
+                let previous = self
+                    .current_note
+                    .checked_sub(1)
+                    .unwrap_or(self.notes.len() - 1);
+                match self.ratios[previous] {
+                    Some(from) => from + (ratio - from) * phase,
+                    None => ratio,
+                }
+            }
+            TrackerEffect::Arpeggio(_, _) => {
+                let arpeggio = &self.arpeggios[self.current_note];
+                arpeggio[((phase * ARPEGGIO_CYCLES * 3.0) as usize) % 3].unwrap_or(ratio)
+            }
+            _ => ratio,
+        })
     }
+
+    fn envelope_phase(&self, phase: f32) -> Option<f32> {
+        let tracker_note = &self.notes[self.current_note];
+        match tracker_note.effect {
+            TrackerEffect::Cut(cut) if phase >= cut => None,
+            TrackerEffect::Retrigger(times) => Some((phase * times.max(1) as f32).fract()),
+            _ => {
+                let (offset, length) = self.ties[self.current_note];
+                Some(((offset + phase * tracker_note.speed.get_beats()) / length).min(0.9999))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Tracker<S: DawSource> {
+    sample_rate: f32,
+    speed: f32,
+    lanes: Vec<TrackerLane<S>>,
+    bpm: Arc<AtomicF32>,
+    table: Vec<f32>,
+    host: Option<Arc<HostTransport>>,
 }
 
-impl<S: DawSource> Tracker<S> {
+impl<S: DawSource + Clone> Tracker<S> {
     #[inline]
     pub fn new(
         sample_rate: f32,
-        bpm: Arc<Mutex<f32>>,
+        bpm: Arc<AtomicF32>,
         notes: Vec<TrackerNote>,
         source: S,
         speed: f32,
         table: Vec<f32>,
+        host: Option<Arc<HostTransport>>,
     ) -> Self {
         Self {
             sample_rate,
             bpm,
-            notes,
-            source,
+            lanes: tracker_lanes(&notes)
+                .into_iter()
+                .map(|x| TrackerLane::new(source.clone(), x))
+                .collect(),
             speed,
-            current_note: 0,
-            last_idx: -1.0,
             table,
+            host,
         }
     }
+
+    /// Plays the pattern in `tuning` rather than 12 tone equal temperament.
+    pub fn tuning(mut self, tuning: &Tuning) -> Self {
+        for lane in self.lanes.iter_mut() {
+            lane.tune(tuning);
+        }
+        self
+    }
+
+    fn table_value(&self, index: f32) -> f32 {
+        let real_idx = index * self.table.len() as f32;
+        let idx = real_idx.floor() as usize;
+        let initial_weight = real_idx - idx as f32;
+        let first = self.table[idx] * initial_weight;
+        let second = if idx + 1 >= self.table.len() {
+            self.table[idx] + 0.001
+        } else {
+            self.table[idx + 1]
+        } * (1.0 - initial_weight);
+        first + second
+    }
 }
 
 impl<S: DawSource + Clone> DawSource for Tracker<S> {
-    fn next(&mut self, mut index: f32, channel: u8) -> Option<f32> {
-        let tracker_note = &self.notes[self.current_note];
-        let seconds_per_note =
-            tracker_note.speed.get_beats() / (self.bpm.lock().map(|x| *x).unwrap_or(120.0) / 60.0);
-        let samples_per_note = seconds_per_note * self.sample_rate;
-        let note = match &tracker_note.note {
-            None => {
-                index /= self.speed;
-                index %= samples_per_note;
-                index /= samples_per_note;
-                if self.last_idx > index {
-                    self.current_note += 1;
-                    if self.current_note >= self.notes.len() {
-                        self.current_note = 0;
-                    }
+    fn next(&mut self, index: f32, channel: u8) -> Option<f32> {
+        let host = self.host.clone().filter(|x| x.is_playing());
+        let samples_per_beat = self.sample_rate / (self.bpm.load() / 60.0);
+        let mut out = None;
+        for lane_idx in 0..self.lanes.len() {
+            let lane = &mut self.lanes[lane_idx];
+            let phase = match &host {
+                Some(host) => {
+                    let (current_note, phase) = lane.host_position(host);
+                    lane.current_note = current_note;
+                    phase
                 }
-                self.last_idx = index;
-                return Some(0.0);
-            }
-            Some(x) => x,
-        };
-        self.source
-            .next(
-                index * Pitch(Octave::O4, note.clone()).match_freq()
-                    / Pitch(Octave::O4, Note::C).match_freq(),
-                channel,
-            )
-            .map(|f| {
-                index /= self.speed;
-                index %= samples_per_note;
-                index /= samples_per_note;
-                if self.last_idx > index {
-                    self.current_note += 1;
-                    if self.current_note >= self.notes.len() {
-                        self.current_note = 0;
-                    }
+                None => {
+                    let samples_per_note =
+                        lane.notes[lane.current_note].speed.get_beats() * samples_per_beat;
+                    lane.step(index, self.speed, samples_per_note)
+                }
+            };
+            let ratio = match lane.ratio(phase) {
+                Some(x) => x,
+                None => {
+                    lane.last_index = None;
+                    out = Some(out.unwrap_or(0.0));
+                    continue;
                 }
-                self.last_idx = index;
-                let real_idx = index * self.table.len() as f32;
-                let idx = real_idx.floor() as usize;
-                let initial_weight = real_idx - idx as f32;
-                let first = self.table[idx] * initial_weight;
-                let second = if idx + 1 >= self.table.len() {
-                    self.table[idx] + 0.001
-                } else {
-                    self.table[idx + 1]
-                } * (1.0 - initial_weight);
-                f * (first + second)
-            })
+            };
+            // The position is accumulated so slides and note changes don't jump the source's
+            // phase.
+            lane.position = match lane.last_index {
+                Some(last) if index >= last => lane.position + (index - last) * ratio,
+                _ => index * ratio,
+            };
+            lane.last_index = Some(index);
+            let velocity = lane.notes[lane.current_note].velocity;
+            let envelope = lane.envelope_phase(phase);
+            let position = lane.position;
+            let sample = lane.source.next(position, channel);
+            if let Some(sample) = sample {
+                let amp = match envelope {
+                    Some(x) => self.table_value(x) * velocity,
+                    None => 0.0,
+                };
+                out = Some(out.unwrap_or(0.0) + sample * amp);
+            }
+        }
+        out
     }
 }
@@ -0,0 +1,126 @@
+use crate::sound_map::{AtomicF32, DawSource};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The host's transport, written by the plugin every sample and read by the graph.
+#[derive(Debug)]
+pub struct HostTransport {
+    beats: AtomicF32,
+    numerator: AtomicF32,
+    denominator: AtomicF32,
+    playing: AtomicBool,
+}
+
+impl Default for HostTransport {
+    fn default() -> Self {
+        Self {
+            beats: AtomicF32::new(0.0),
+            numerator: AtomicF32::new(4.0),
+            denominator: AtomicF32::new(4.0),
+            playing: AtomicBool::new(false),
+        }
+    }
+}
+
+impl HostTransport {
+    pub fn set_signature(&self, numerator: f32, denominator: f32) {
+        self.numerator.store(numerator.max(1.0));
+        self.denominator.store(denominator.max(1.0));
+    }
+
+    pub fn set_playing(&self, playing: bool) {
+        self.playing.store(playing, Ordering::Relaxed);
+    }
+
+    /// The song position in quarter notes.
+    pub fn set_beats(&self, beats: f32) {
+        self.beats.store(beats);
+    }
+
+    pub fn beats(&self) -> f32 {
+        self.beats.load()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing.load(Ordering::Relaxed)
+    }
+
+    pub fn numerator(&self) -> f32 {
+        self.numerator.load()
+    }
+
+    pub fn denominator(&self) -> f32 {
+        self.denominator.load()
+    }
+
+    /// The length of a bar in quarter notes. Bars are counted from the start of the song, so a
+    /// time signature change part way through isn't accounted for.
+    pub fn bar_length(&self) -> f32 {
+        self.numerator() * 4.0 / self.denominator()
+    }
+
+    pub fn bar(&self) -> f32 {
+        (self.beats() / self.bar_length()).floor()
+    }
+
+    /// How far through the current beat of the time signature the song is, 0 to 1.
+    pub fn beat_phase(&self) -> f32 {
+        (self.beats() * self.denominator() / 4.0).rem_euclid(1.0)
+    }
+}
+
+/// How far through a note of `note_beats` quarter notes a tempo synced node is, 0 to 1. Follows
+/// the host's song position while it plays if the node is locked to it, otherwise the time since
+/// the voice started.
+#[inline]
+pub fn sync_phase(
+    host: Option<&Arc<HostTransport>>,
+    index: f32,
+    speed: f32,
+    note_beats: f32,
+    samples_per_note: f32,
+) -> f32 {
+    match host.filter(|x| x.is_playing()) {
+        Some(host) => (host.beats() / note_beats).rem_euclid(1.0).min(0.9999),
+        None => ((index / speed) % samples_per_note) / samples_per_note,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransportValue {
+    Beats,
+    Bar,
+    BeatPhase,
+    Numerator,
+    Denominator,
+    Playing,
+}
+
+#[derive(Clone, Debug)]
+pub struct TransportSource {
+    transport: Arc<HostTransport>,
+    value: TransportValue,
+}
+
+impl TransportSource {
+    #[inline]
+    pub fn new(transport: Arc<HostTransport>, value: TransportValue) -> Self {
+        Self { transport, value }
+    }
+}
+
+impl DawSource for TransportSource {
+    fn next(&mut self, _index: f32, _channel: u8) -> Option<f32> {
+        Some(match self.value {
+            TransportValue::Beats => self.transport.beats(),
+            TransportValue::Bar => self.transport.bar(),
+            TransportValue::BeatPhase => self.transport.beat_phase(),
+            TransportValue::Numerator => self.transport.numerator(),
+            TransportValue::Denominator => self.transport.denominator(),
+            TransportValue::Playing => match self.transport.is_playing() {
+                true => 1.0,
+                false => 0.0,
+            },
+        })
+    }
+}
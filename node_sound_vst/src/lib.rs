@@ -5,26 +5,65 @@ use nih_plug_egui::{EguiState, create_egui_editor};
 use node_sound_core::sound_map::DawSource;
 use node_sound_core::{
     constants::MIDDLE_C_FREQ,
-    nodes::get_nodes,
     sound_graph::{
         self,
+        arpeggiator::{ArpMode, ArpSettings, Arpeggiator, MAX_ARP_OCTAVES},
+        audition::{Audition, SharedAudition, render_node_source},
+        automation::{
+            AUTOMATION_COUNT, AutomationSmoothing, MacroSnapshot, macro_string_to_value,
+            macro_value_to_string,
+        },
+        compiler::{DEBOUNCE, compile, detached_state},
         copy_paste_del_helpers::{copy, delete_nodes, paste},
-        graph::{ActiveNodeState, FileManager, SoundNodeGraph, evaluate_node},
-        graph_types::ValueType,
+        graph::{
+            ActiveNodeState, DAWAutomations, DAWInput, DAWTransport, DAWTuning, FileManager,
+            SoundNodeGraph,
+        },
+        note::NoteSpeed,
+        scope::ScopeTap,
+        tuning::TuningTable,
+        voice_mode::{
+            HeldNotes, MAX_GLIDE_MS, MAX_POLYPHONY, NotePriority, StealPolicy, VoiceMode,
+            VoiceSettings,
+        },
     },
-    sound_map::GenericSource,
+    sound_map::{AtomicF32, GenericSource},
     sounds::{const_wave::ConstWave, speed::Speed},
 };
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
-use util::midi_note_to_freq;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-const NUM_VOICES: u32 = 16;
 const GAIN_POLY_MOD_ID: u32 = 0;
 const MAX_BLOCK_SIZE: usize = 64;
 const MIDI_NOTES_LEN: u8 = 128;
+/// Compiled ahead of time so notes that are still compiling always have a neighbour to fall back
+/// on.
+const WARM_NOTE: u8 = 60;
+/// With the CPU guard on, no new voices start while a block takes more than this much of the
+/// time it plays for to render.
+const CPU_GUARD_LOAD: f32 = 0.8;
+/// Voices are ended once their envelope has fallen below this.
+const ENVELOPE_THRESHOLD: f32 = 1e-5;
+
+type SourceSoundBuffers = Arc<Mutex<[Option<GenericSource>; MIDI_NOTES_LEN as usize]>>;
+
+/// Notes that were played before their sources were compiled, picked up by the compile task.
+struct NoteRequests([AtomicBool; MIDI_NOTES_LEN as usize]);
+
+/// How many voices are playing, for the editor's bottom bar.
+#[derive(Default)]
+struct VoiceMeter {
+    active: AtomicUsize,
+    /// Set while the CPU guard is holding back new voices.
+    limited: AtomicBool,
+}
+
+impl Default for NoteRequests {
+    fn default() -> Self {
+        Self([0; MIDI_NOTES_LEN as usize].map(|_| AtomicBool::new(false)))
+    }
+}
 
 /// Data for a single synth voice. In a real synth where performance matter, you may want to use a
 /// struct of arrays instead of having a struct for each voice.
@@ -42,30 +81,181 @@ struct Voice {
     /// The voices internal ID. Each voice has an internal voice ID one higher than the previous
     /// voice. This is used to steal the last voice in case all 16 voices are in use.
     internal_voice_id: u64,
-    /// The square root of the note's velocity. This is used as a gain multiplier.
-    velocity_sqrt: f32,
+    /// The square root of the note's velocity, scaled by the velocity amount. This is used as a
+    /// gain multiplier.
+    velocity_gain: f32,
     /// Whether the key has been released and the voice is in its release stage. The voice will be
     /// terminated when the amplitude envelope hits 0 while the note is releasing.
     releasing: bool,
-    /// Fades between 0 and 1 with timings based on the global attack and release settings.
-    amp_envelope: Smoother<f32>,
+    /// Runs from 0 to 1 and back with the envelope settings from when the note started.
+    amp_envelope: AmpEnvelope,
 
     /// If this voice has polyphonic gain modulation applied, then this contains the normalized
     /// offset and a smoother.
     voice_gain: Option<(f32, Smoother<f32>)>,
 
     voice_source: GenericSource,
+    /// The note `voice_source` was compiled for, which differs from `note` after a legato glide.
+    source_note: u8,
+    /// The playback speed relative to `source_note`, slides between notes in the mono modes.
+    glide: Smoother<f32>,
+    /// How far gliding has moved the playback position away from `voice_idx`.
+    glide_offset: f32,
 
     voice_idx: usize,
+    /// How fast `voice_source` moves through the graph's index, which its input nodes read the
+    /// input history in.
+    source_speed: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EnvelopeStage {
+    Attack,
+    Hold,
+    Decay,
+    Sustain,
+    Release,
+    Done,
+}
+
+/// The stage lengths of an amp envelope in samples, and the shapes of its ramps.
+#[derive(Clone, Copy, Debug)]
+struct EnvelopeShape {
+    attack: f32,
+    hold: f32,
+    decay: f32,
+    sustain: f32,
+    release: f32,
+    attack_curve: f32,
+    decay_curve: f32,
+    release_curve: f32,
+}
+
+/// Bends a ramp's progress from 0 to 1. Below zero the ramp moves fast at first and eases in
+/// like an exponential, above zero it starts slow.
+fn envelope_curve(progress: f32, curve: f32) -> f32 {
+    progress.clamp(0.0, 1.0).powf(4.0_f32.powf(curve))
+}
+
+#[derive(Clone, Debug)]
+struct AmpEnvelope {
+    shape: EnvelopeShape,
+    stage: EnvelopeStage,
+    /// Samples into the current stage.
+    position: f32,
+    /// The level the current stage started from, a release can start part way through a ramp.
+    from: f32,
+    value: f32,
+}
+
+impl AmpEnvelope {
+    fn new(shape: EnvelopeShape) -> Self {
+        Self {
+            shape,
+            stage: EnvelopeStage::Attack,
+            position: 0.0,
+            from: 0.0,
+            value: 0.0,
+        }
+    }
+
+    fn next(&mut self) -> f32 {
+        let shape = self.shape;
+        let (length, to, curve, next_stage) = match self.stage {
+            EnvelopeStage::Attack => (shape.attack, 1.0, shape.attack_curve, EnvelopeStage::Hold),
+            EnvelopeStage::Hold => (shape.hold, 1.0, 0.0, EnvelopeStage::Decay),
+            EnvelopeStage::Decay => (
+                shape.decay,
+                shape.sustain,
+                shape.decay_curve,
+                EnvelopeStage::Sustain,
+            ),
+            EnvelopeStage::Release => {
+                (shape.release, 0.0, shape.release_curve, EnvelopeStage::Done)
+            }
+            EnvelopeStage::Sustain | EnvelopeStage::Done => return self.value,
+        };
+        if self.position >= length {
+            self.stage = next_stage;
+            self.position = 0.0;
+            self.from = to;
+            self.value = to;
+            return self.value;
+        }
+        self.value = self.from + (to - self.from) * envelope_curve(self.position / length, curve);
+        self.position += 1.0;
+        self.value
+    }
+
+    fn release(&mut self) {
+        if self.stage != EnvelopeStage::Done {
+            self.stage = EnvelopeStage::Release;
+            self.position = 0.0;
+            self.from = self.value;
+        }
+    }
+
+    /// Whether the envelope has faded out after its attack, so the voice can be ended.
+    fn is_silent(&self) -> bool {
+        match self.stage {
+            EnvelopeStage::Attack | EnvelopeStage::Hold => false,
+            EnvelopeStage::Done => true,
+            _ => self.value < ENVELOPE_THRESHOLD,
+        }
+    }
+}
+
+impl Voice {
+    /// Starts the voice's release stage, which ends it once the envelope reaches zero.
+    fn release(&mut self) {
+        self.releasing = true;
+        self.amp_envelope.release();
+    }
+
+    /// How loud the voice is right now, for stealing the quietest.
+    fn level(&self) -> f32 {
+        self.amp_envelope.value * self.velocity_gain
+    }
+
+    /// Slides the playback speed to `speed` over `glide_ms`.
+    fn glide_to(&mut self, speed: f32, glide_ms: f32, sample_rate: f32) {
+        if glide_ms > 0.0 {
+            self.glide.style = SmoothingStyle::Linear(glide_ms);
+            self.glide.set_target(sample_rate, speed);
+        } else {
+            self.glide.reset(speed);
+        }
+    }
 }
 
 pub struct NodeSound {
     params: Arc<NodeSoundParams>,
-    voices: [Option<Voice>; NUM_VOICES as usize],
+    voices: [Option<Voice>; MAX_POLYPHONY],
     next_internal_voice_id: u64,
-    sample_rate: Arc<Mutex<f32>>,
-    bpm: Arc<Mutex<f32>>,
-    source_sound_buffers: Arc<Mutex<[Option<GenericSource>; MIDI_NOTES_LEN as usize]>>,
+    sample_rate: Arc<AtomicF32>,
+    bpm: Arc<AtomicF32>,
+    /// The graph's shared handles from the last block the graph could be locked in, so the audio
+    /// thread never has to wait for the editor.
+    automations: DAWAutomations,
+    transport: DAWTransport,
+    tuning: DAWTuning,
+    input: DAWInput,
+    automation_macros: [MacroSnapshot; AUTOMATION_COUNT],
+    automation_smoothers: [Smoother<f32>; AUTOMATION_COUNT],
+    /// The value and smoothing each automation smoother was last given, as setting a target
+    /// restarts its ramp.
+    automation_targets: [(f32, AutomationSmoothing); AUTOMATION_COUNT],
+    scope_tap: ScopeTap,
+    source_sound_buffers: SourceSoundBuffers,
+    note_requests: Arc<NoteRequests>,
+    audition: SharedAudition,
+    arpeggiator: Arpeggiator,
+    /// Whether the arpeggiator was on in the last block.
+    arp_enabled: bool,
+    held_notes: HeldNotes,
+    voice_meter: Arc<VoiceMeter>,
+    /// Whether the last block took too long to render, for the CPU guard.
+    overloaded: bool,
 }
 
 pub struct PluginPresetState {
@@ -90,8 +280,54 @@ pub struct NodeSoundParams {
     /// The amplitude envelope release time. This is the same for every voice.
     #[id = "amp_rel"]
     amp_release_ms: FloatParam,
+    /// How long the envelope stays at full level after the attack.
+    #[id = "amp_hold"]
+    amp_hold_ms: FloatParam,
+    #[id = "amp_dec"]
+    amp_decay_ms: FloatParam,
+    #[id = "amp_sus"]
+    amp_sustain: FloatParam,
+    #[id = "amp_atk_curve"]
+    amp_attack_curve: FloatParam,
+    #[id = "amp_dec_curve"]
+    amp_decay_curve: FloatParam,
+    #[id = "amp_rel_curve"]
+    amp_release_curve: FloatParam,
+    /// How much the note's velocity sets its level, at zero every note plays at full level.
+    #[id = "amp_vel"]
+    amp_velocity: FloatParam,
+    /// How much the envelope's times shorten for notes above middle C and lengthen below it. At
+    /// one they halve each octave.
+    #[id = "amp_key"]
+    amp_key_tracking: FloatParam,
+    /// Plays the left channel out of both sides.
     #[id = "is_mono"]
     is_mono: BoolParam,
+    #[id = "voice_mode"]
+    voice_mode: IntParam,
+    #[id = "priority"]
+    note_priority: IntParam,
+    #[id = "glide"]
+    glide_ms: FloatParam,
+    #[id = "polyphony"]
+    polyphony: IntParam,
+    #[id = "stealing"]
+    steal_policy: IntParam,
+    #[id = "cpu_guard"]
+    cpu_guard: BoolParam,
+    #[id = "arp"]
+    arp_enabled: BoolParam,
+    #[id = "arp_mode"]
+    arp_mode: IntParam,
+    #[id = "arp_oct"]
+    arp_octaves: IntParam,
+    /// An index into `NoteSpeed::ALL`.
+    #[id = "arp_rate"]
+    arp_rate: IntParam,
+    #[id = "arp_gate"]
+    arp_gate: FloatParam,
+    #[id = "arp_latch"]
+    arp_latch: BoolParam,
     #[id = "a1"]
     pub a1: FloatParam,
     #[id = "a2"]
@@ -128,7 +364,6 @@ pub struct NodeSoundParams {
     pub a17: FloatParam,
     #[id = "a18"]
     pub a18: FloatParam,
-    root_sound_id: Arc<Mutex<Option<usize>>>,
 }
 
 impl<'a> PersistentField<'a, String> for PluginPresetState {
@@ -153,17 +388,33 @@ impl Default for NodeSound {
         let params = NodeSoundParams::default();
         Self {
             params: Arc::new(params),
-            voices: [0; NUM_VOICES as usize].map(|_| None),
+            voices: [0; MAX_POLYPHONY].map(|_| None),
             next_internal_voice_id: 0,
-            sample_rate: Arc::new(Mutex::new(48000.0)),
-            bpm: Arc::new(Mutex::new(120.0)),
+            sample_rate: Arc::new(AtomicF32::new(48000.0)),
+            bpm: Arc::new(AtomicF32::new(120.0)),
+            automations: DAWAutomations::default(),
+            transport: DAWTransport::default(),
+            tuning: DAWTuning::default(),
+            input: DAWInput::default(),
+            automation_macros: [MacroSnapshot::default(); AUTOMATION_COUNT],
+            automation_smoothers: [0; AUTOMATION_COUNT]
+                .map(|_| Smoother::new(SmoothingStyle::None)),
+            automation_targets: [(0.0, AutomationSmoothing::None); AUTOMATION_COUNT],
+            scope_tap: ScopeTap::default(),
             source_sound_buffers: Arc::new(Mutex::new([0; MIDI_NOTES_LEN as usize].map(|_| None))),
+            note_requests: Arc::new(NoteRequests::default()),
+            audition: Arc::new(Mutex::new(None)),
+            arpeggiator: Arpeggiator::default(),
+            arp_enabled: false,
+            held_notes: HeldNotes::default(),
+            voice_meter: Arc::new(VoiceMeter::default()),
+            overloaded: false,
         }
     }
 }
 
 macro_rules! mkparam {
-    ($field: ident, $name: literal) => {
+    ($field: ident, $name: literal, $idx: literal, $graph: ident) => {
         let $field = FloatParam::new(
             $name,
             0.0,
@@ -173,39 +424,272 @@ macro_rules! mkparam {
             },
         )
         .with_smoother(SmoothingStyle::None)
-        .with_step_size(0.01);
+        .with_step_size(0.01)
+        .with_value_to_string(macro_value_to_string($graph.clone(), $idx))
+        .with_string_to_value(macro_string_to_value($graph.clone(), $idx));
     };
 }
 
+impl NodeSoundParams {
+    fn automation_params(&self) -> [&FloatParam; AUTOMATION_COUNT] {
+        [
+            &self.a1, &self.a2, &self.a3, &self.a4, &self.a5, &self.a6, &self.a7, &self.a8,
+            &self.a9, &self.a10, &self.a11, &self.a12, &self.a13, &self.a14, &self.a15, &self.a16,
+            &self.a17, &self.a18,
+        ]
+    }
+
+    fn arp_settings(&self) -> ArpSettings {
+        ArpSettings {
+            enabled: self.arp_enabled.value(),
+            mode: ArpMode::ALL[self.arp_mode.value() as usize % ArpMode::ALL.len()],
+            octaves: self.arp_octaves.value() as u8,
+            rate: NoteSpeed::ALL[self.arp_rate.value() as usize % NoteSpeed::ALL.len()].clone(),
+            gate: self.arp_gate.value(),
+            latch: self.arp_latch.value(),
+            loaded: false,
+        }
+    }
+
+    fn set_arp_settings(&self, setter: &ParamSetter, arp: &ArpSettings) {
+        let mode = ArpMode::ALL.iter().position(|x| *x == arp.mode);
+        let rate = NoteSpeed::ALL.iter().position(|x| *x == arp.rate);
+        setter.set_parameter(&self.arp_enabled, arp.enabled);
+        setter.set_parameter(&self.arp_mode, mode.unwrap_or(0) as i32);
+        setter.set_parameter(&self.arp_octaves, arp.octaves as i32);
+        setter.set_parameter(&self.arp_rate, rate.unwrap_or(0) as i32);
+        setter.set_parameter(&self.arp_gate, arp.gate);
+        setter.set_parameter(&self.arp_latch, arp.latch);
+    }
+
+    /// The amp envelope for a note, with its times scaled by the key tracking.
+    fn amp_envelope_shape(&self, note: u8, sample_rate: f32) -> EnvelopeShape {
+        let scale = 2.0_f32.powf(-(note as f32 - 60.0) / 12.0 * self.amp_key_tracking.value());
+        let samples = |ms: f32| ms / 1000.0 * sample_rate * scale;
+        EnvelopeShape {
+            attack: samples(self.amp_attack_ms.value()),
+            hold: samples(self.amp_hold_ms.value()),
+            decay: samples(self.amp_decay_ms.value()),
+            sustain: self.amp_sustain.value(),
+            release: samples(self.amp_release_ms.value()),
+            attack_curve: self.amp_attack_curve.value(),
+            decay_curve: self.amp_decay_curve.value(),
+            release_curve: self.amp_release_curve.value(),
+        }
+    }
+
+    fn voice_settings(&self) -> VoiceSettings {
+        VoiceSettings {
+            mode: VoiceMode::ALL[self.voice_mode.value() as usize % VoiceMode::ALL.len()],
+            priority: NotePriority::ALL
+                [self.note_priority.value() as usize % NotePriority::ALL.len()],
+            glide_ms: self.glide_ms.value(),
+            polyphony: self.polyphony.value() as usize,
+            stealing: StealPolicy::ALL[self.steal_policy.value() as usize % StealPolicy::ALL.len()],
+            cpu_guard: self.cpu_guard.value(),
+            loaded: false,
+        }
+    }
+
+    fn set_voice_settings(&self, setter: &ParamSetter, voices: &VoiceSettings) {
+        let mode = VoiceMode::ALL.iter().position(|x| *x == voices.mode);
+        let priority = NotePriority::ALL.iter().position(|x| *x == voices.priority);
+        setter.set_parameter(&self.voice_mode, mode.unwrap_or(0) as i32);
+        setter.set_parameter(&self.note_priority, priority.unwrap_or(0) as i32);
+        setter.set_parameter(&self.glide_ms, voices.glide_ms);
+        let stealing = StealPolicy::ALL.iter().position(|x| *x == voices.stealing);
+        setter.set_parameter(&self.polyphony, voices.polyphony as i32);
+        setter.set_parameter(&self.steal_policy, stealing.unwrap_or(0) as i32);
+        setter.set_parameter(&self.cpu_guard, voices.cpu_guard);
+    }
+}
+
+/// The envelope curves start out bent like the exponential envelope they replaced.
+fn curve_param(name: &'static str) -> FloatParam {
+    FloatParam::new(
+        name,
+        -0.5,
+        FloatRange::Linear {
+            min: -1.0,
+            max: 1.0,
+        },
+    )
+    .with_step_size(0.01)
+}
+
+fn smoothing_style(smoothing: AutomationSmoothing) -> SmoothingStyle {
+    match smoothing {
+        AutomationSmoothing::None => SmoothingStyle::None,
+        AutomationSmoothing::Linear(ms) => SmoothingStyle::Linear(ms),
+        AutomationSmoothing::Exponential(ms) => SmoothingStyle::Exponential(ms),
+    }
+}
+
 impl Default for NodeSoundParams {
     fn default() -> Self {
-        mkparam! {a1, "A1"}
-        mkparam! {a2, "A2"}
-        mkparam! {a3, "A3"}
-        mkparam! {a4, "A4"}
-        mkparam! {a5, "A5"}
-        mkparam! {a6, "A6"}
-        mkparam! {a7, "A7"}
-        mkparam! {a8, "A8"}
-        mkparam! {a9, "A9"}
-        mkparam! {a10, "A10"}
-        mkparam! {a11, "A11"}
-        mkparam! {a12, "A12"}
-        mkparam! {a13, "A13"}
-        mkparam! {a14, "A14"}
-        mkparam! {a15, "A15"}
-        mkparam! {a16, "A16"}
-        mkparam! {a17, "A17"}
-        mkparam! {a18, "A18"}
+        let graph = Arc::new(Mutex::new(
+            sound_graph::graph::SoundNodeGraph::new_vst_synth(),
+        ));
+        mkparam! {a1, "A1", 0, graph}
+        mkparam! {a2, "A2", 1, graph}
+        mkparam! {a3, "A3", 2, graph}
+        mkparam! {a4, "A4", 3, graph}
+        mkparam! {a5, "A5", 4, graph}
+        mkparam! {a6, "A6", 5, graph}
+        mkparam! {a7, "A7", 6, graph}
+        mkparam! {a8, "A8", 7, graph}
+        mkparam! {a9, "A9", 8, graph}
+        mkparam! {a10, "A10", 9, graph}
+        mkparam! {a11, "A11", 10, graph}
+        mkparam! {a12, "A12", 11, graph}
+        mkparam! {a13, "A13", 12, graph}
+        mkparam! {a14, "A14", 13, graph}
+        mkparam! {a15, "A15", 14, graph}
+        mkparam! {a16, "A16", 15, graph}
+        mkparam! {a17, "A17", 16, graph}
+        mkparam! {a18, "A18", 17, graph}
 
+        let arp = ArpSettings::default();
+        let voices = VoiceSettings::default();
         Self {
-            is_mono: BoolParam::new("Mono", false),
+            is_mono: BoolParam::new("Mono Output", false),
+            voice_mode: IntParam::new(
+                "Voice Mode",
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: VoiceMode::ALL.len() as i32 - 1,
+                },
+            )
+            .with_value_to_string(Arc::new(|x| {
+                VoiceMode::ALL[x as usize % VoiceMode::ALL.len()]
+                    .name()
+                    .to_string()
+            }))
+            .with_string_to_value(Arc::new(|x| {
+                VoiceMode::ALL
+                    .iter()
+                    .position(|mode| mode.name() == x.trim())
+                    .map(|x| x as i32)
+            })),
+            note_priority: IntParam::new(
+                "Note Priority",
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: NotePriority::ALL.len() as i32 - 1,
+                },
+            )
+            .with_value_to_string(Arc::new(|x| {
+                NotePriority::ALL[x as usize % NotePriority::ALL.len()]
+                    .name()
+                    .to_string()
+            }))
+            .with_string_to_value(Arc::new(|x| {
+                NotePriority::ALL
+                    .iter()
+                    .position(|priority| priority.name() == x.trim())
+                    .map(|x| x as i32)
+            })),
+            glide_ms: FloatParam::new(
+                "Glide",
+                voices.glide_ms,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: MAX_GLIDE_MS,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" ms"),
+            polyphony: IntParam::new(
+                "Polyphony",
+                voices.polyphony as i32,
+                IntRange::Linear {
+                    min: 1,
+                    max: MAX_POLYPHONY as i32,
+                },
+            ),
+            steal_policy: IntParam::new(
+                "Voice Stealing",
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: StealPolicy::ALL.len() as i32 - 1,
+                },
+            )
+            .with_value_to_string(Arc::new(|x| {
+                StealPolicy::ALL[x as usize % StealPolicy::ALL.len()]
+                    .name()
+                    .to_string()
+            }))
+            .with_string_to_value(Arc::new(|x| {
+                StealPolicy::ALL
+                    .iter()
+                    .position(|stealing| stealing.name() == x.trim())
+                    .map(|x| x as i32)
+            })),
+            cpu_guard: BoolParam::new("CPU Guard", voices.cpu_guard),
+            arp_enabled: BoolParam::new("Arp", arp.enabled),
+            arp_mode: IntParam::new(
+                "Arp Mode",
+                0,
+                IntRange::Linear {
+                    min: 0,
+                    max: ArpMode::ALL.len() as i32 - 1,
+                },
+            )
+            .with_value_to_string(Arc::new(|x| {
+                ArpMode::ALL[x as usize % ArpMode::ALL.len()]
+                    .name()
+                    .to_string()
+            }))
+            .with_string_to_value(Arc::new(|x| {
+                ArpMode::ALL
+                    .iter()
+                    .position(|mode| mode.name() == x.trim())
+                    .map(|x| x as i32)
+            })),
+            arp_octaves: IntParam::new(
+                "Arp Octaves",
+                arp.octaves as i32,
+                IntRange::Linear {
+                    min: 1,
+                    max: MAX_ARP_OCTAVES as i32,
+                },
+            ),
+            arp_rate: IntParam::new(
+                "Arp Rate",
+                NoteSpeed::ALL
+                    .iter()
+                    .position(|x| *x == arp.rate)
+                    .unwrap_or(0) as i32,
+                IntRange::Linear {
+                    min: 0,
+                    max: NoteSpeed::ALL.len() as i32 - 1,
+                },
+            )
+            .with_value_to_string(Arc::new(|x| {
+                NoteSpeed::ALL[x as usize % NoteSpeed::ALL.len()].to_string()
+            }))
+            .with_string_to_value(Arc::new(|x| {
+                NoteSpeed::ALL
+                    .iter()
+                    .position(|rate| rate.to_string() == x.trim())
+                    .map(|x| x as i32)
+            })),
+            arp_gate: FloatParam::new(
+                "Arp Gate",
+                arp.gate,
+                FloatRange::Linear {
+                    min: 0.01,
+                    max: 1.0,
+                },
+            )
+            .with_step_size(0.01),
+            arp_latch: BoolParam::new("Arp Latch", arp.latch),
             editor_state: EguiState::from_size(1280, 720),
-            plugin_state: PluginPresetState {
-                graph: Arc::new(Mutex::new(
-                    sound_graph::graph::SoundNodeGraph::new_vst_synth(),
-                )),
-            },
+            plugin_state: PluginPresetState { graph },
             gain: FloatParam::new(
                 "Gain",
                 util::db_to_gain(-12.0),
@@ -242,7 +726,45 @@ impl Default for NodeSoundParams {
             )
             .with_step_size(0.1)
             .with_unit(" ms"),
-            root_sound_id: Arc::new(Mutex::new(None)),
+            amp_hold_ms: FloatParam::new(
+                "Hold",
+                0.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 2000.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" ms"),
+            amp_decay_ms: FloatParam::new(
+                "Decay",
+                300.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 5000.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_step_size(0.1)
+            .with_unit(" ms"),
+            amp_sustain: FloatParam::new("Sustain", 1.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_step_size(0.01),
+            amp_attack_curve: curve_param("Attack Curve"),
+            amp_decay_curve: curve_param("Decay Curve"),
+            amp_release_curve: curve_param("Release Curve"),
+            amp_velocity: FloatParam::new(
+                "Velocity",
+                1.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_step_size(0.01),
+            amp_key_tracking: FloatParam::new(
+                "Key Tracking",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_step_size(0.01),
             a1,
             a2,
             a3,
@@ -278,8 +800,8 @@ impl NodeSound {
             .position(|voice| matches!(voice, Some(voice) if voice.voice_id == voice_id))
     }
 
-    /// Start a new voice with the given voice ID. If all voices are currently in use, the oldest
-    /// voice will be stolen. Returns a reference to the new voice.
+    /// Start a new voice with the given voice ID. If the polyphony is used up, a voice is stolen
+    /// according to the stealing policy. Returns a reference to the new voice.
     fn start_voice(
         &mut self,
         context: &mut impl ProcessContext<Self>,
@@ -290,69 +812,215 @@ impl NodeSound {
         velocity: f32,
         sample_rate: f32,
     ) -> &mut Voice {
-        let amp_envelope = Smoother::new(SmoothingStyle::Exponential(
-            self.params.amp_attack_ms.value(),
-        ));
-        amp_envelope.reset(0.0);
-        amp_envelope.set_target(sample_rate, 1.0);
+        let amp_envelope = AmpEnvelope::new(self.params.amp_envelope_shape(note, sample_rate));
+        let voice_source = match self.source_sound_buffers.lock() {
+            // Keys the tuning leaves out are played as silence.
+            Ok(_) if self.tuning.0.frequency(note).is_none() => None,
+            Ok(buffers) => match &buffers[note as usize] {
+                Some(x) => Some(x.clone()),
+                None => {
+                    if !self.note_requests.0[note as usize].swap(true, Ordering::AcqRel) {
+                        context.execute_background(BackgroundTasks::CompileNotes);
+                    }
+                    nearest_source(&buffers, &self.tuning.0, note)
+                }
+            },
+            Err(_x) => None,
+        };
         let new_voice = Voice {
             voice_id: voice_id.unwrap_or_else(|| compute_fallback_voice_id(note, channel)),
             internal_voice_id: self.next_internal_voice_id,
             channel,
             note,
-            velocity_sqrt: velocity.sqrt(),
+            velocity_gain: 1.0 - self.params.amp_velocity.value() * (1.0 - velocity.sqrt()),
             releasing: false,
             amp_envelope,
             voice_gain: None,
             voice_idx: 0,
-            voice_source: self
-                .source_sound_buffers
-                .lock()
-                .expect("expected lock on source sound buffers")[note as usize]
-                .clone()
-                .unwrap_or(GenericSource::new(Box::new(ConstWave::new(0.0)))),
+            voice_source: voice_source.unwrap_or(GenericSource::new(Box::new(ConstWave::new(0.0)))),
+            source_note: note,
+            source_speed: note_speed(&self.tuning.0, note).unwrap_or(1.0),
+            glide: Smoother::new(SmoothingStyle::None),
+            glide_offset: 0.0,
         };
+        new_voice.glide.reset(1.0);
 
         self.next_internal_voice_id = self.next_internal_voice_id.wrapping_add(1);
 
-        match self.voices.iter().position(|voice| voice.is_none()) {
-            Some(free_voice_idx) => {
-                self.voices[free_voice_idx] = Some(new_voice);
-                return self.voices[free_voice_idx].as_mut().unwrap();
+        // Lowering the polyphony while notes are playing can leave more than one voice to steal.
+        let settings = self.params.voice_settings();
+        let polyphony = settings.polyphony.clamp(1, MAX_POLYPHONY);
+        while self.voices.iter().flatten().count() >= polyphony {
+            let stolen_idx = match self.voice_to_steal(settings.stealing, channel, note) {
+                Some(x) => x,
+                None => break,
+            };
+            if let Some(stolen) = self.voices[stolen_idx].take() {
+                context.send_event(NoteEvent::VoiceTerminated {
+                    timing: sample_offset,
+                    voice_id: Some(stolen.voice_id),
+                    channel: stolen.channel,
+                    note: stolen.note,
+                });
             }
-            None => {
-                let oldest_voice = unsafe {
-                    self.voices
-                        .iter_mut()
-                        .min_by_key(|voice| voice.as_ref().unwrap_unchecked().internal_voice_id)
-                        .unwrap_unchecked()
-                };
+        }
+        let free_voice_idx = self
+            .voices
+            .iter()
+            .position(|voice| voice.is_none())
+            .unwrap_or(0);
+        self.voices[free_voice_idx] = Some(new_voice);
+        self.voices[free_voice_idx].as_mut().unwrap()
+    }
 
-                {
-                    let oldest_voice = oldest_voice.as_ref().unwrap();
-                    context.send_event(NoteEvent::VoiceTerminated {
-                        timing: sample_offset,
-                        voice_id: Some(oldest_voice.voice_id),
-                        channel: oldest_voice.channel,
-                        note: oldest_voice.note,
-                    });
-                }
+    /// Picks the voice that gives way to a new note on `channel` and `note`.
+    fn voice_to_steal(&self, policy: StealPolicy, channel: u8, note: u8) -> Option<usize> {
+        let voices = || {
+            self.voices
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, voice)| Some((idx, voice.as_ref()?)))
+        };
+        let oldest = || voices().min_by_key(|x| x.1.internal_voice_id);
+        let stolen = match policy {
+            StealPolicy::Oldest => oldest(),
+            StealPolicy::Quietest => voices().min_by(|a, b| a.1.level().total_cmp(&b.1.level())),
+            StealPolicy::SameNote => voices()
+                .filter(|x| x.1.channel == channel && x.1.note == note)
+                .min_by_key(|x| x.1.internal_voice_id)
+                .or_else(oldest),
+            StealPolicy::ReleaseFirst => voices()
+                .filter(|x| x.1.releasing)
+                .min_by(|a, b| a.1.level().total_cmp(&b.1.level()))
+                .or_else(oldest),
+        };
+        stolen.map(|x| x.0)
+    }
 
-                *oldest_voice = Some(new_voice);
-                return oldest_voice.as_mut().unwrap();
+    /// Plays a note on a voice of its own, or on the mono voice in the mono modes.
+    fn play_note(
+        &mut self,
+        context: &mut impl ProcessContext<Self>,
+        sample_offset: u32,
+        voice_id: Option<i32>,
+        channel: u8,
+        note: u8,
+        velocity: f32,
+    ) {
+        let settings = self.params.voice_settings();
+        let previous = self.held_notes.current(settings.priority);
+        self.held_notes.note_on(channel, note, velocity);
+        match settings.mode {
+            // The CPU guard never silences the synth entirely.
+            VoiceMode::Poly
+                if settings.cpu_guard
+                    && self.overloaded
+                    && self.voices.iter().any(|x| x.is_some()) => {}
+            VoiceMode::Poly => {
+                let sample_rate = self.sample_rate.load();
+                self.start_voice(
+                    context,
+                    sample_offset,
+                    voice_id,
+                    channel,
+                    note,
+                    velocity,
+                    sample_rate,
+                );
             }
+            _ => self.update_mono_voice(context, sample_offset, voice_id, &settings, previous),
         }
     }
 
-    /// Start the release process for one or more voice by changing their amplitude envelope. If
-    /// `voice_id` is not provided, then this will terminate all matching voices.
-    fn start_release_for_voices(
+    /// Lets go of a note. In the mono modes this goes back to the next held note, if there is one.
+    fn release_note(
         &mut self,
-        sample_rate: f32,
+        context: &mut impl ProcessContext<Self>,
+        sample_offset: u32,
         voice_id: Option<i32>,
         channel: u8,
         note: u8,
     ) {
+        let settings = self.params.voice_settings();
+        let previous = self.held_notes.current(settings.priority);
+        self.held_notes.note_off(note);
+        match settings.mode {
+            VoiceMode::Poly => {
+                self.start_release_for_voices(voice_id, channel, note);
+            }
+            _ => self.update_mono_voice(context, sample_offset, None, &settings, previous),
+        }
+    }
+
+    /// Moves the mono voice onto the held note the priority picks, after a key went down or up.
+    /// `previous` is the note that was picked before it. Legato glides the sounding voice to the
+    /// new pitch, mono starts a new voice that glides from where the last one was.
+    fn update_mono_voice(
+        &mut self,
+        context: &mut impl ProcessContext<Self>,
+        sample_offset: u32,
+        voice_id: Option<i32>,
+        settings: &VoiceSettings,
+        previous: Option<(u8, u8, f32)>,
+    ) {
+        let next = self.held_notes.current(settings.priority);
+        if previous.map(|x| x.1) == next.map(|x| x.1) {
+            return;
+        }
+        let sample_rate = self.sample_rate.load();
+        let tuning = self.tuning.0.clone();
+        let sounding = self
+            .voices
+            .iter()
+            .position(|x| matches!(x, Some(voice) if !voice.releasing));
+        let from_speed = match (previous, sounding) {
+            (Some(_), Some(idx)) => self.voices[idx].as_ref().and_then(|voice| {
+                Some(note_speed(&tuning, voice.source_note)? * voice.glide.previous_value())
+            }),
+            _ => None,
+        };
+        let (channel, note, velocity) = match next {
+            Some(x) => x,
+            None => {
+                for voice in self.voices.iter_mut().flatten().filter(|x| !x.releasing) {
+                    voice.release();
+                }
+                return;
+            }
+        };
+        if let (VoiceMode::Legato, Some(idx), Some(_)) = (settings.mode, sounding, from_speed) {
+            let voice = self.voices[idx].as_mut().unwrap();
+            if let (Some(to), Some(source)) = (
+                note_speed(&tuning, note),
+                note_speed(&tuning, voice.source_note),
+            ) {
+                voice.channel = channel;
+                voice.note = note;
+                voice.glide_to(to / source, settings.glide_ms, sample_rate);
+                return;
+            }
+        }
+        for voice in self.voices.iter_mut().flatten().filter(|x| !x.releasing) {
+            voice.release();
+        }
+        let voice = self.start_voice(
+            context,
+            sample_offset,
+            voice_id,
+            channel,
+            note,
+            velocity,
+            sample_rate,
+        );
+        if let (Some(from), Some(to)) = (from_speed, note_speed(&tuning, note)) {
+            voice.glide.reset(from / to);
+            voice.glide_to(1.0, settings.glide_ms, sample_rate);
+        }
+    }
+
+    /// Start the release process for one or more voice by changing their amplitude envelope. If
+    /// `voice_id` is not provided, then this will terminate all matching voices.
+    fn start_release_for_voices(&mut self, voice_id: Option<i32>, channel: u8, note: u8) {
         for voice in self.voices.iter_mut() {
             match voice {
                 Some(Voice {
@@ -366,9 +1034,7 @@ impl NodeSound {
                     || (channel == *candidate_channel && note == *candidate_note) =>
                 {
                     *releasing = true;
-                    amp_envelope.style =
-                        SmoothingStyle::Exponential(self.params.amp_release_ms.value());
-                    amp_envelope.set_target(sample_rate, 0.0);
+                    amp_envelope.release();
 
                     // If this targetted a single voice ID, we're done here. Otherwise there may be
                     // multiple overlapping voices as we enabled support for that in the
@@ -422,16 +1088,111 @@ impl NodeSound {
     }
 }
 
-macro_rules! mkparamgetter {
-    ($field: ident, $idx: literal, $self: ident, $buff: ident) => {
-        let $field = $self.params.$field.value();
-        match $buff[$idx].lock() {
-            Ok(mut x) => {
-                *x = $field;
-            }
+/// The playback speed for MIDI note `note` under the patch's tuning, relative to the pitch the
+/// graph is built at. None for keys the tuning leaves silent.
+fn note_speed(tuning: &TuningTable, note: u8) -> Option<f32> {
+    let frequency = tuning.frequency(note)?;
+    Some(
+        from_semitones(
+            MIDDLE_C_FREQ,
+            to_semitones(frequency, MIDDLE_C_FREQ) + 10.5 + 1.8 - 0.5 + 0.2 + 0.1,
+        ) / MIDDLE_C_FREQ,
+    )
+}
+
+/// Stands in for a note that is still compiling by pitching the closest compiled note.
+fn nearest_source(
+    buffers: &[Option<GenericSource>; MIDI_NOTES_LEN as usize],
+    tuning: &TuningTable,
+    note: u8,
+) -> Option<GenericSource> {
+    let nearest = (0..MIDI_NOTES_LEN)
+        .filter(|x| buffers[*x as usize].is_some())
+        .min_by_key(|x| (*x as i16 - note as i16).abs())?;
+    let sound = buffers[nearest as usize].clone()?;
+    Some(GenericSource::new(Box::new(Speed::new(
+        sound,
+        note_speed(tuning, note)? / note_speed(tuning, nearest)?,
+    ))))
+}
+
+/// Evaluates the patch for every requested note and stores the results. Runs as a background
+/// task, so playing a note that hasn't been compiled yet never blocks the audio thread.
+fn compile_requested_notes(
+    graph: &Mutex<SoundNodeGraph>,
+    buffers: &SourceSoundBuffers,
+    requests: &NoteRequests,
+    sample_rate: f32,
+    bpm: Arc<AtomicF32>,
+) {
+    let mut notes = (0..MIDI_NOTES_LEN)
+        .filter(|x| requests.0[*x as usize].swap(false, Ordering::AcqRel))
+        .collect::<Vec<_>>();
+    let is_empty = match buffers.lock() {
+        Ok(x) => x.iter().all(|x| x.is_none()),
+        Err(_x) => return,
+    };
+    if is_empty && !notes.contains(&WARM_NOTE) {
+        notes.push(WARM_NOTE);
+    }
+    loop {
+        let (job, tuning, revision) = match graph.lock() {
+            Ok(x) => (
+                detached_state(&x.state, sample_rate, bpm.clone()),
+                x.state._unserializeable_state.tuning.clone(),
+                x.state.user_state.history.revision(),
+            ),
+            Err(_x) => return,
+        };
+        let speeds = notes
+            .iter()
+            .filter_map(|x| Some((*x as usize, note_speed(&tuning.0, *x)?)))
+            .collect::<Vec<_>>();
+        let result = compile(job, &speeds);
+        // The graph is held while storing, so the editor can't store a newer compile in between.
+        let current = match graph.lock() {
+            Ok(x) => x,
+            Err(_x) => return,
+        };
+        if current.state.user_state.history.revision() != revision {
+            // The graph changed while compiling, so these sources are already out of date.
+            continue;
+        }
+        match result {
+            Ok(sources) => store_sources(buffers, &tuning.0, sources),
             Err(_x) => {}
         }
-    };
+        return;
+    }
+}
+
+fn store_sources(
+    buffers: &SourceSoundBuffers,
+    tuning: &TuningTable,
+    sources: Vec<(usize, GenericSource)>,
+) {
+    let mut sources = sources
+        .into_iter()
+        .filter_map(|(note, sound)| {
+            let speed = note_speed(tuning, note as u8)?;
+            Some((
+                note,
+                Some(GenericSource::new(Box::new(Speed::new(sound, speed)))),
+            ))
+        })
+        .collect::<Vec<_>>();
+    // Playing voices keep their own copy of the old sources, so swapping the buffers only
+    // affects notes started from here on. The lock is held for the swap alone, the new sources
+    // are built and the old ones dropped outside of it, as the audio thread waits on it.
+    match buffers.lock() {
+        Ok(mut sound_buffers) => {
+            for (note, sound) in sources.iter_mut() {
+                std::mem::swap(&mut sound_buffers[*note], sound);
+            }
+        }
+        Err(_x) => {}
+    }
+    drop(sources);
 }
 
 fn to_semitones(f1: f32, f2: f32) -> f32 {
@@ -444,6 +1205,11 @@ fn from_semitones(f2: f32, n: f32) -> f32 {
 pub enum BackgroundTasks {
     MidiFileOpen(Arc<Mutex<FileManager>>),
     WavFileOpen(Arc<Mutex<FileManager>>),
+    PatchFileDialog(Arc<Mutex<FileManager>>),
+    RecordFileDialog(Arc<Mutex<FileManager>>),
+    MidiExportDialog(Arc<Mutex<FileManager>>),
+    TuningFileDialog(Arc<Mutex<FileManager>>),
+    CompileNotes,
 }
 
 impl Plugin for NodeSound {
@@ -471,7 +1237,12 @@ impl Plugin for NodeSound {
     }
 
     fn task_executor(&mut self) -> TaskExecutor<Self> {
-        Box::new(|cx| match cx {
+        let graph = self.params.plugin_state.graph.clone();
+        let buffers = self.source_sound_buffers.clone();
+        let requests = self.note_requests.clone();
+        let sample_rate = self.sample_rate.clone();
+        let bpm = self.bpm.clone();
+        Box::new(move |cx| match cx {
             BackgroundTasks::MidiFileOpen(files) => {
                 match files.lock() {
                     Err(_x) => {}
@@ -518,6 +1289,39 @@ impl Plugin for NodeSound {
                     }
                 };
             }
+            BackgroundTasks::PatchFileDialog(files) => {
+                match files.lock() {
+                    Err(_x) => {}
+                    Ok(mut x) => x.open_patch_dialogs(),
+                };
+            }
+            BackgroundTasks::RecordFileDialog(files) => {
+                match files.lock() {
+                    Err(_x) => {}
+                    Ok(mut x) => x.open_record_dialog(),
+                };
+            }
+            BackgroundTasks::MidiExportDialog(files) => {
+                match files.lock() {
+                    Err(_x) => {}
+                    Ok(mut x) => x.open_midi_export_dialog(),
+                };
+            }
+            BackgroundTasks::TuningFileDialog(files) => {
+                match files.lock() {
+                    Err(_x) => {}
+                    Ok(mut x) => x.open_tuning_dialog(),
+                };
+            }
+            BackgroundTasks::CompileNotes => {
+                compile_requested_notes(
+                    &graph,
+                    &buffers,
+                    &requests,
+                    sample_rate.load(),
+                    bpm.clone(),
+                );
+            }
         })
     }
 
@@ -528,45 +1332,52 @@ impl Plugin for NodeSound {
                 self.params.plugin_state.graph.clone(),
                 self.source_sound_buffers.clone(),
                 self.sample_rate.clone(),
-                self.params.root_sound_id.clone(),
                 false,
                 self.bpm.clone(),
                 None,
                 self.params.clone(),
+                self.audition.clone(),
+                self.voice_meter.clone(),
             ),
             |_, _| {},
             move |egui_ctx, setter, state| {
-                let sound_result_id = &mut match state.3.lock() {
-                    Ok(x) => x,
-                    Err(_x) => {
-                        return;
-                    }
-                };
-                let sample_rate = &mut match state.2.lock() {
-                    Ok(x) => x,
-                    Err(_x) => {
-                        return;
-                    }
-                };
-                let sound_buffers = &mut match state.1.lock() {
-                    Ok(x) => x,
-                    Err(_x) => {
-                        return;
-                    }
-                };
+                let sample_rate = state.2.load();
                 let graph = &mut match state.0.lock() {
                     Ok(x) => x,
                     Err(_x) => {
                         return;
                     }
                 };
-                let error: &mut Option<String> = &mut state.6;
+                let error: &mut Option<String> = &mut state.5;
                 graph.update_root(egui_ctx);
 
-                let mut gain = state.7.gain.value();
-                let mut attack = state.7.amp_attack_ms.value();
-                let mut release = state.7.amp_release_ms.value();
-                let mut mono = state.7.is_mono.value();
+                let mut gain = state.6.gain.value();
+                let mut attack = state.6.amp_attack_ms.value();
+                let mut release = state.6.amp_release_ms.value();
+                let mut hold = state.6.amp_hold_ms.value();
+                let mut decay = state.6.amp_decay_ms.value();
+                let mut sustain = state.6.amp_sustain.value();
+                let mut attack_curve = state.6.amp_attack_curve.value();
+                let mut decay_curve = state.6.amp_decay_curve.value();
+                let mut release_curve = state.6.amp_release_curve.value();
+                let mut velocity = state.6.amp_velocity.value();
+                let mut key_tracking = state.6.amp_key_tracking.value();
+                let mut mono = state.6.is_mono.value();
+                let mut arp = state.6.arp_settings();
+                let stored_arp = &mut graph.state.user_state.arpeggiator;
+                if stored_arp.loaded {
+                    // A patch was just loaded, so its settings win over the parameters.
+                    stored_arp.loaded = false;
+                    arp = stored_arp.clone();
+                }
+                let mut arp_changed = false;
+                let mut voices = state.6.voice_settings();
+                let stored_voices = &mut graph.state.user_state.voices;
+                if stored_voices.loaded {
+                    stored_voices.loaded = false;
+                    voices = stored_voices.clone();
+                }
+                let mut voices_changed = false;
 
                 egui::TopBottomPanel::bottom("bottom").show(egui_ctx, |ui| {
                     egui::menu::bar(ui, |ui| {
@@ -577,14 +1388,52 @@ impl Plugin for NodeSound {
                         };
                         if ui
                             .add(
-                                egui::Button::new("Mono")
+                                egui::Button::new("Mono Out")
                                     .fill(mono_color)
                                     .min_size(egui::Vec2::new(60.0, 20.0)),
                             )
+                            .on_hover_text("Play the left channel out of both sides")
                             .clicked()
                         {
                             mono = !mono;
                         }
+                        let voice_menu = ui
+                            .menu_button(format!("{} ⏷", voices.mode.name()), |ui| voices.ui(ui));
+                        voice_menu
+                            .response
+                            .on_hover_text("Voice mode, note priority and glide");
+                        voices_changed |= voice_menu.inner == Some(true);
+                        let voice_count = format!(
+                            "Voices: {}/{}",
+                            state.8.active.load(Ordering::Relaxed),
+                            voices.polyphony
+                        );
+                        if state.8.limited.load(Ordering::Relaxed) {
+                            ui.colored_label(ui.visuals().warn_fg_color, voice_count)
+                                .on_hover_text("The CPU guard is holding back new voices");
+                        } else {
+                            ui.label(voice_count);
+                        }
+                        let arp_color = if arp.enabled {
+                            ui.visuals().code_bg_color
+                        } else {
+                            ui.visuals().extreme_bg_color
+                        };
+                        if ui
+                            .add(
+                                egui::Button::new("Arp")
+                                    .fill(arp_color)
+                                    .min_size(egui::Vec2::new(60.0, 20.0)),
+                            )
+                            .clicked()
+                        {
+                            arp.enabled = !arp.enabled;
+                            arp_changed = true;
+                        }
+                        let arp_menu = ui.menu_button("⏷", |ui| arp.ui(ui));
+                        arp_menu.response.on_hover_text("Arpeggiator settings");
+                        arp_changed |= arp_menu.inner == Some(true);
+                        ui.separator();
                         ui.label("Gain:");
                         ui.add(
                             AudioKnob::new(&mut gain)
@@ -601,6 +1450,30 @@ impl Plugin for NodeSound {
                                 .diameter(20.0),
                         );
                         ui.separator();
+                        ui.label("Hold:");
+                        ui.add(
+                            AudioKnob::new(&mut hold)
+                                .range(0.0..=2000.0)
+                                .drag_length(20.0)
+                                .diameter(20.0),
+                        );
+                        ui.separator();
+                        ui.label("Decay:");
+                        ui.add(
+                            AudioKnob::new(&mut decay)
+                                .range(0.0..=5000.0)
+                                .drag_length(20.0)
+                                .diameter(20.0),
+                        );
+                        ui.separator();
+                        ui.label("Sustain:");
+                        ui.add(
+                            AudioKnob::new(&mut sustain)
+                                .range(0.0..=1.0)
+                                .drag_length(20.0)
+                                .diameter(20.0),
+                        );
+                        ui.separator();
                         ui.label("Release:");
                         ui.add(
                             AudioKnob::new(&mut release)
@@ -608,6 +1481,27 @@ impl Plugin for NodeSound {
                                 .drag_length(20.0)
                                 .diameter(20.0),
                         );
+                        ui.menu_button("⏷", |ui| {
+                            egui::Grid::new("amp_envelope_settings").show(ui, |ui| {
+                                ui.label("attack curve");
+                                ui.add(egui::Slider::new(&mut attack_curve, -1.0..=1.0));
+                                ui.end_row();
+                                ui.label("decay curve");
+                                ui.add(egui::Slider::new(&mut decay_curve, -1.0..=1.0));
+                                ui.end_row();
+                                ui.label("release curve");
+                                ui.add(egui::Slider::new(&mut release_curve, -1.0..=1.0));
+                                ui.end_row();
+                                ui.label("velocity");
+                                ui.add(egui::Slider::new(&mut velocity, 0.0..=1.0));
+                                ui.end_row();
+                                ui.label("key tracking");
+                                ui.add(egui::Slider::new(&mut key_tracking, 0.0..=1.0));
+                                ui.end_row();
+                            });
+                        })
+                        .response
+                        .on_hover_text("Envelope curves, velocity and key tracking");
                         ui.separator();
                         match error {
                             Some(x) => {
@@ -617,41 +1511,53 @@ impl Plugin for NodeSound {
                         };
                     })
                 });
-                setter.set_parameter(&state.7.gain, gain);
-                setter.set_parameter(&state.7.amp_attack_ms, attack);
-                setter.set_parameter(&state.7.amp_release_ms, release);
-                setter.set_parameter(&state.7.is_mono, mono);
+                setter.set_parameter(&state.6.gain, gain);
+                setter.set_parameter(&state.6.amp_attack_ms, attack);
+                setter.set_parameter(&state.6.amp_release_ms, release);
+                setter.set_parameter(&state.6.amp_hold_ms, hold);
+                setter.set_parameter(&state.6.amp_decay_ms, decay);
+                setter.set_parameter(&state.6.amp_sustain, sustain);
+                setter.set_parameter(&state.6.amp_attack_curve, attack_curve);
+                setter.set_parameter(&state.6.amp_decay_curve, decay_curve);
+                setter.set_parameter(&state.6.amp_release_curve, release_curve);
+                setter.set_parameter(&state.6.amp_velocity, velocity);
+                setter.set_parameter(&state.6.amp_key_tracking, key_tracking);
+                setter.set_parameter(&state.6.is_mono, mono);
+                state.6.set_arp_settings(setter, &arp);
+                state.6.set_voice_settings(setter, &voices);
+                if graph.state.user_state.voices != voices {
+                    graph.state.user_state.voices = voices;
+                    if voices_changed {
+                        graph.state.user_state.is_saved = false;
+                    }
+                }
+                if graph.state.user_state.arpeggiator != arp {
+                    graph.state.user_state.arpeggiator = arp;
+                    if arp_changed {
+                        graph.state.user_state.is_saved = false;
+                    }
+                }
 
                 egui::TopBottomPanel::bottom("automations").show(egui_ctx, |ui| {
                     egui::menu::bar(ui, |ui| {
                         ui.label("Automations: ");
                         egui::ScrollArea::horizontal().show(ui, |ui| {
                             ui.horizontal(|ui| {
-                                let params = &state.7;
-                                let knobs = [
-                                    ("A1", &params.a1),
-                                    ("A2", &params.a2),
-                                    ("A3", &params.a3),
-                                    ("A4", &params.a4),
-                                    ("A5", &params.a5),
-                                    ("A6", &params.a6),
-                                    ("A7", &params.a7),
-                                    ("A8", &params.a8),
-                                    ("A9", &params.a9),
-                                    ("A10", &params.a10),
-                                    ("A11", &params.a11),
-                                    ("A12", &params.a12),
-                                    ("A13", &params.a13),
-                                    ("A14", &params.a14),
-                                    ("A15", &params.a15),
-                                    ("A16", &params.a16),
-                                    ("A17", &params.a17),
-                                    ("A18", &params.a18),
-                                ];
-
-                                for (label, param) in knobs.iter() {
+                                let params = &state.6;
+                                let macros = &mut graph.state.user_state.automation_macros;
+                                if ui.button("⚙").on_hover_text("Edit macros").clicked() {
+                                    macros.open = !macros.open;
+                                }
+                                let mut changed = false;
+                                for (idx, param) in
+                                    params.automation_params().into_iter().enumerate()
+                                {
+                                    let automation_macro = match macros.get_mut(idx) {
+                                        Some(x) => x,
+                                        None => break,
+                                    };
                                     ui.vertical(|ui| {
-                                        ui.label(*label);
+                                        ui.label(automation_macro.name.clone());
                                         let param_value = param.value();
                                         let mut current_value = param_value;
                                         let response = ui.add(
@@ -660,106 +1566,117 @@ impl Plugin for NodeSound {
                                                 .drag_length(50.0)
                                                 .diameter(15.0),
                                         );
+                                        let response = response
+                                            .on_hover_text(automation_macro.format(current_value));
                                         if response.changed() && current_value != param_value {
-                                            setter.set_parameter(*param, current_value);
+                                            setter.set_parameter(param, current_value);
                                         }
+                                        let smoothing = &mut automation_macro.smoothing;
+                                        let name = smoothing.name();
+                                        let menu = ui.menu_button("~", |ui| smoothing.ui(ui, idx));
+                                        menu.response.on_hover_text(format!("Smoothing: {}", name));
+                                        changed |= menu.inner == Some(true);
                                     });
                                     ui.add_space(2.0);
                                 }
+                                if changed {
+                                    graph.state.user_state.is_saved = false;
+                                }
                             });
                         });
                     });
                 });
 
                 // refreshes the graph state to fix bugs with DAW automations that if not refreshed will be null secretly...
-                if !state.4 {
-                    state.4 = true;
+                if !state.3 {
+                    state.3 = true;
                     let copy_state = copy(&mut graph.state.editor_state, true);
                     delete_nodes(&mut graph.state.editor_state, true);
                     executor::block_on(paste(&mut graph.state.editor_state, None, copy_state));
                 }
-                if sound_result_id.is_none() || graph.state.user_state.active_node.is_playing() {
-                    let mut clear = false;
-                    graph.state.user_state.active_node = ActiveNodeState::NoNode;
-                    match graph.state.user_state.vst_output_node_id {
-                        Some(outputid) => {
+                let mut recompile = false;
+                if graph.state.user_state.active_modified {
+                    graph.state.user_state.active_modified = false;
+                    recompile = true;
+                    let audition = match graph.state.user_state.active_node {
+                        ActiveNodeState::PlayingNode(node_id) => {
                             graph
                                 .state
                                 ._unserializeable_state
                                 .queue
-                                .set_bpm(state.5.clone());
-                            graph.state.user_state.wavetables.clear();
-                            for vidx in 0..MIDI_NOTES_LEN as usize {
-                                let speed = from_semitones(
-                                    MIDDLE_C_FREQ,
-                                    to_semitones(midi_note_to_freq(vidx as u8), MIDDLE_C_FREQ)
-                                        + 10.5
-                                        + 1.8
-                                        - 0.5
-                                        + 0.2
-                                        + 0.1,
-                                ) / MIDDLE_C_FREQ;
-                                graph.state._unserializeable_state.queue.clear();
-                                graph
-                                    .state
-                                    ._unserializeable_state
-                                    .queue
-                                    .set_note_speed(speed);
-                                graph
-                                    .state
-                                    ._unserializeable_state
-                                    .queue
-                                    .set_sample_rate(**sample_rate);
-                                match evaluate_node(
-                                    &graph.state.editor_state.graph.clone(),
-                                    outputid,
-                                    &mut HashMap::new(),
-                                    &get_nodes(),
-                                    &mut graph.state,
-                                ) {
-                                    Ok(val) => {
-                                        *error = None;
-                                        let source_id = match val {
-                                            ValueType::AudioSource { value } => value,
-                                            _ => {
-                                                return;
-                                            }
-                                        };
-                                        **sound_result_id = Some(source_id);
-                                        let sound = match graph
-                                            .state
-                                            ._unserializeable_state
-                                            .queue
-                                            .clone_sound(source_id.clone())
-                                        {
-                                            Err(_err) => {
-                                                GenericSource::new(Box::new(ConstWave::new(0.0)))
-                                            }
-                                            Ok(x) => x,
-                                        };
-                                        sound_buffers[vidx] = Some(GenericSource::new(Box::new(
-                                            Speed::new(sound, speed),
-                                        )));
-                                        graph.state._unserializeable_state.queue.clear();
-                                    }
-                                    Err(err) => {
-                                        *error = Some(format!("{:?}", err));
-                                        clear = true;
-                                    }
-                                };
+                                .set_sample_rate(sample_rate);
+                            graph
+                                .state
+                                ._unserializeable_state
+                                .queue
+                                .set_bpm(state.4.clone());
+                            match render_node_source(&mut graph.state, node_id) {
+                                Ok(x) => Some(Audition::new(x)),
+                                Err(err) => {
+                                    *error = Some(format!("{:?}", err));
+                                    None
+                                }
                             }
                         }
-                        None => {
-                            clear = true;
-                        }
+                        _ => None,
+                    };
+                    match state.7.lock() {
+                        Ok(mut x) => *x = audition,
+                        Err(_x) => {}
                     }
-                    if clear {
-                        for buffer in (**sound_buffers).iter_mut() {
-                            *buffer = None;
+                }
+                let revision = graph.state.user_state.history.revision();
+                let compiler = &mut graph.state._unserializeable_state.compiler;
+                if recompile {
+                    compiler.invalidate();
+                }
+                if compiler.should_compile(revision) {
+                    // Only notes that have been played are recompiled, the rest are compiled on
+                    // first use by the compile task.
+                    let mut notes = match state.1.lock() {
+                        Ok(x) => (0..MIDI_NOTES_LEN)
+                            .filter(|note| x[*note as usize].is_some())
+                            .collect::<Vec<_>>(),
+                        Err(_x) => vec![],
+                    };
+                    if notes.is_empty() {
+                        notes.push(WARM_NOTE);
+                    }
+                    // Building the job brings the tuning table up to date for the note speeds.
+                    let job = detached_state(&graph.state, sample_rate, state.4.clone());
+                    let tuning = graph.state._unserializeable_state.tuning.clone();
+                    let notes = notes
+                        .into_iter()
+                        .filter_map(|x| Some((x as usize, note_speed(&tuning.0, x)?)))
+                        .collect();
+                    graph
+                        .state
+                        ._unserializeable_state
+                        .compiler
+                        .start(job, revision, notes);
+                }
+                let tuning = graph.state._unserializeable_state.tuning.clone();
+                let compiler = &mut graph.state._unserializeable_state.compiler;
+                match compiler.try_finish() {
+                    Some(Ok(sources)) => {
+                        *error = None;
+                        store_sources(&state.1, &tuning.0, sources);
+                    }
+                    Some(Err(err)) => {
+                        *error = Some(err);
+                        match state.1.lock() {
+                            Ok(mut sound_buffers) => {
+                                for buffer in sound_buffers.iter_mut() {
+                                    *buffer = None;
+                                }
+                            }
+                            Err(_x) => {}
                         }
-                        graph.state._unserializeable_state.queue.clear();
-                        **sound_result_id = None
                     }
+                    None => {}
+                }
+                if !compiler.is_idle(revision) {
+                    egui_ctx.request_repaint_after(DEBOUNCE / 4);
                 }
             },
         )
@@ -771,6 +1688,27 @@ impl Plugin for NodeSound {
         _buffer_config: &BufferConfig,
         _context: &mut impl InitContext<Self>,
     ) -> bool {
+        // Loading a state replaces the graph and reinitializes the plugin, so this covers the
+        // history of every graph the audio thread reads from.
+        if let Ok(graph) = self.params.plugin_state.graph.lock() {
+            graph.state._unserializeable_state.input.0.allocate();
+        }
+        for (param, smoother) in self
+            .params
+            .automation_params()
+            .iter()
+            .zip(self.automation_smoothers.iter())
+        {
+            smoother.reset(param.value());
+        }
+        for (param, target) in self
+            .params
+            .automation_params()
+            .iter()
+            .zip(self.automation_targets.iter_mut())
+        {
+            target.0 = param.value();
+        }
         true
     }
 
@@ -781,18 +1719,19 @@ impl Plugin for NodeSound {
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
         let num_samples = buffer.samples();
-        let automations;
-        {
-            let state = &match self.params.plugin_state.graph.lock() {
-                Ok(x) => x,
-                Err(_x) => {
-                    return ProcessStatus::KeepAlive;
-                }
-            }
-            .state;
-            automations = state._unserializeable_state.automations.0.clone();
+        let render_start = Instant::now();
+        // The editor holds the graph while it draws, in which case the handles from the last
+        // block are used.
+        if let Ok(graph) = self.params.plugin_state.graph.try_lock() {
+            let state = &graph.state;
+            self.automations = state._unserializeable_state.automations.clone();
+            self.transport = state._unserializeable_state.transport.clone();
+            self.tuning = state._unserializeable_state.tuning.clone();
+            self.input = state._unserializeable_state.input.clone();
+            self.automation_macros = state.user_state.automation_macros.snapshot();
+            self.scope_tap = state.user_state.scopes.tap.clone();
 
-            match state.user_state.files.lock() {
+            match state.user_state.files.try_lock() {
                 Ok(x) => {
                     if x.midi_active.is_some() {
                         context.execute_background(BackgroundTasks::MidiFileOpen(
@@ -804,33 +1743,74 @@ impl Plugin for NodeSound {
                             state.user_state.files.clone(),
                         ));
                     }
+                    if x.has_patch_dialog() {
+                        context.execute_background(BackgroundTasks::PatchFileDialog(
+                            state.user_state.files.clone(),
+                        ));
+                    }
+                    if x.record_active.is_some() {
+                        context.execute_background(BackgroundTasks::RecordFileDialog(
+                            state.user_state.files.clone(),
+                        ));
+                    }
+                    if x.midi_export_active.is_some() {
+                        context.execute_background(BackgroundTasks::MidiExportDialog(
+                            state.user_state.files.clone(),
+                        ));
+                    }
+                    if x.tuning_active.is_some() {
+                        context.execute_background(BackgroundTasks::TuningFileDialog(
+                            state.user_state.files.clone(),
+                        ));
+                    }
                 }
-                Err(_x) => {
-                    return ProcessStatus::KeepAlive;
-                }
+                Err(_x) => {}
             }
         }
 
-        let sample_rate = match self.sample_rate.lock() {
-            Ok(mut x) => {
-                *x = context.transport().sample_rate;
-                *x
-            }
-            Err(_x) => {
-                return ProcessStatus::KeepAlive;
+        let sample_rate = context.transport().sample_rate;
+        self.sample_rate.store(sample_rate);
+        self.bpm
+            .store(context.transport().tempo.unwrap_or(120.0) as f32);
+        let transport = context.transport();
+        let song_beats = transport.pos_beats().unwrap_or_default();
+        let beats_per_sample = transport.tempo.unwrap_or(120.0) / 60.0 / sample_rate as f64;
+        self.transport.0.set_playing(transport.playing);
+        self.transport.0.set_signature(
+            transport.time_sig_numerator.unwrap_or(4) as f32,
+            transport.time_sig_denominator.unwrap_or(4) as f32,
+        );
+        // The smoothers only get a new target when the value changes, otherwise their ramps would
+        // restart every block. They are stepped every sample.
+        for (idx, param) in self.params.automation_params().iter().enumerate() {
+            let smoother = &mut self.automation_smoothers[idx];
+            let (target, smoothing) = &mut self.automation_targets[idx];
+            let macro_smoothing = self.automation_macros[idx].smoothing;
+            if *smoothing != macro_smoothing {
+                *smoothing = macro_smoothing;
+                smoother.style = smoothing_style(macro_smoothing);
             }
-        };
-        match self.bpm.lock() {
-            Ok(mut x) => {
-                *x = context.transport().tempo.unwrap_or(120.0) as f32;
-                *x
+            if *target != param.value() {
+                *target = param.value();
+                smoother.set_target(sample_rate, param.value());
             }
-            Err(_x) => {
-                return ProcessStatus::KeepAlive;
-            }
-        };
+        }
+        let input = self.input.0.clone();
+        let scope_tap = self.scope_tap.clone();
         let output = buffer.as_slice();
+        let audition = self.audition.clone();
+        let mut audition = audition.try_lock().ok();
 
+        let arp_settings = self.params.arp_settings();
+        if arp_settings.enabled && !self.arp_enabled {
+            // Note offs go to the arpeggiator while it's on, so the notes that were already
+            // playing would never be let go.
+            self.held_notes.clear();
+            for voice in self.voices.iter_mut().flatten() {
+                voice.release();
+            }
+        }
+        self.arp_enabled = arp_settings.enabled;
         let mut next_event = context.next_event();
         let mut block_start: usize = 0;
         let mut block_end: usize = MAX_BLOCK_SIZE.min(num_samples);
@@ -852,25 +1832,27 @@ impl Plugin for NodeSound {
                                 note,
                                 velocity,
                             } => {
-                                self.start_voice(
-                                    context,
-                                    timing,
-                                    voice_id,
-                                    channel,
-                                    note,
-                                    velocity,
-                                    sample_rate,
-                                );
+                                if arp_settings.enabled {
+                                    self.arpeggiator.note_on(channel, note, velocity);
+                                } else {
+                                    self.play_note(
+                                        context, timing, voice_id, channel, note, velocity,
+                                    );
+                                }
                                 notes_to_reset.push(note);
                             }
                             NoteEvent::NoteOff {
-                                timing: _,
+                                timing,
                                 voice_id,
                                 channel,
                                 note,
                                 velocity: _,
                             } => {
-                                self.start_release_for_voices(sample_rate, voice_id, channel, note)
+                                if arp_settings.enabled {
+                                    self.arpeggiator.note_off(note, arp_settings.latch);
+                                } else {
+                                    self.release_note(context, timing, voice_id, channel, note)
+                                }
                             }
                             NoteEvent::Choke {
                                 timing,
@@ -994,39 +1976,47 @@ impl Plugin for NodeSound {
                 .len() as f32)
                 .sqrt();
             for sample_idx in block_start..block_end {
+                self.transport
+                    .0
+                    .set_beats((song_beats + sample_idx as f64 * beats_per_sample) as f32);
+                for ((smoother, automation_macro), automation) in self
+                    .automation_smoothers
+                    .iter()
+                    .zip(self.automation_macros.iter())
+                    .zip(self.automations.0.iter())
+                {
+                    automation.store(automation_macro.map(smoother.next()));
+                }
+                let arp = self.arpeggiator.tick(&arp_settings, beats_per_sample);
+                if let Some((channel, note)) = arp.note_off {
+                    self.release_note(context, sample_idx as u32, None, channel, note);
+                }
+                if let Some((channel, note, velocity)) = arp.note_on {
+                    self.play_note(context, sample_idx as u32, None, channel, note, velocity);
+                }
+                // The buffer starts out holding the input, it goes into the history before the
+                // voices are mixed in.
+                input.push([[output[0][sample_idx], output[1][sample_idx]], [0.0, 0.0]]);
+                output[0][sample_idx] = 0.0;
+                output[1][sample_idx] = 0.0;
                 for voice in &mut self.voices.iter_mut().filter_map(|v| v.as_mut()) {
                     let gain = match &voice.voice_gain {
                         Some((_, smoother)) => smoother.next(),
                         None => 1.0,
                     };
-                    let amp = voice.amp_envelope.next() * voice.velocity_sqrt * gain;
-                    mkparamgetter!(a1, 0, self, automations);
-                    mkparamgetter!(a2, 1, self, automations);
-                    mkparamgetter!(a3, 2, self, automations);
-                    mkparamgetter!(a4, 3, self, automations);
-                    mkparamgetter!(a5, 4, self, automations);
-                    mkparamgetter!(a6, 5, self, automations);
-                    mkparamgetter!(a7, 6, self, automations);
-                    mkparamgetter!(a8, 7, self, automations);
-                    mkparamgetter!(a9, 8, self, automations);
-                    mkparamgetter!(a10, 9, self, automations);
-                    mkparamgetter!(a11, 10, self, automations);
-                    mkparamgetter!(a12, 11, self, automations);
-                    mkparamgetter!(a13, 12, self, automations);
-                    mkparamgetter!(a14, 13, self, automations);
-                    mkparamgetter!(a15, 14, self, automations);
-                    mkparamgetter!(a16, 15, self, automations);
-                    mkparamgetter!(a17, 16, self, automations);
-                    mkparamgetter!(a18, 17, self, automations);
+                    let amp = voice.amp_envelope.next() * voice.velocity_gain * gain;
+                    let time_index = (voice.voice_idx + sample_idx) as f32 + voice.glide_offset;
+                    voice.glide_offset += voice.glide.next() - 1.0;
+                    // The voice's source is pitched, so the graph sees the index scaled by its speed
+                    // and the input nodes have to be told the same index to hear the live input.
+                    input.set_now(time_index * voice.source_speed);
                     if self.params.is_mono.value() {
-                        let time_index = (voice.voice_idx + sample_idx) as f32;
                         let mut left_sample =
                             voice.voice_source.next(time_index, 0).unwrap_or_default() * amp;
                         left_sample /= active_voices;
                         output[0][sample_idx] += left_sample.clamp(-1.0, 1.0);
                         output[1][sample_idx] += left_sample.clamp(-1.0, 1.0);
                     } else {
-                        let time_index = (voice.voice_idx + sample_idx) as f32;
                         let mut left_sample =
                             voice.voice_source.next(time_index, 0).unwrap_or_default() * amp;
                         let mut right_sample =
@@ -1037,12 +2027,23 @@ impl Plugin for NodeSound {
                         output[1][sample_idx] += right_sample.clamp(-1.0, 1.0);
                     }
                 }
+                let mut auditioning = false;
+                if let Some(Some(audition)) = audition.as_deref_mut() {
+                    let (left_sample, right_sample) = audition.next();
+                    output[0][sample_idx] += left_sample;
+                    output[1][sample_idx] += right_sample;
+                    auditioning = true;
+                }
+                if active_voices > 0.0 || auditioning {
+                    scope_tap
+                        .0
+                        .push((output[0][sample_idx] + output[1][sample_idx]) * 0.5);
+                }
             }
 
-            const ENVELOPE_THRESHOLD: f32 = 1e-5;
             for voice in self.voices.iter_mut() {
                 match voice {
-                    Some(v) if v.amp_envelope.previous_value() < ENVELOPE_THRESHOLD => {
+                    Some(v) if v.amp_envelope.is_silent() => {
                         // This event is very important, as it allows the host to manage its own modulation
                         // voices
                         context.send_event(NoteEvent::VoiceTerminated {
@@ -1070,6 +2071,16 @@ impl Plugin for NodeSound {
             }
         }
 
+        let block_seconds = num_samples as f32 / sample_rate;
+        self.overloaded = render_start.elapsed().as_secs_f32() > block_seconds * CPU_GUARD_LOAD;
+        self.voice_meter
+            .active
+            .store(self.voices.iter().flatten().count(), Ordering::Relaxed);
+        self.voice_meter.limited.store(
+            self.overloaded && self.params.cpu_guard.value(),
+            Ordering::Relaxed,
+        );
+
         ProcessStatus::Normal
     }
 }
@@ -1,25 +1,41 @@
 use egui_extras_xt::knobs::AudioKnob;
 use nih_plug::{params::persist::PersistentField, prelude::*};
 use nih_plug_egui::{EguiState, create_egui_editor};
-use node_sound_core::sound_graph::graph::FileManager;
+use node_sound_core::sound_graph::graph::{DAWAutomations, DAWInput, DAWTransport, FileManager};
+use node_sound_core::sound_graph::scope::ScopeTap;
 use node_sound_core::sound_map::DawSource;
 use node_sound_core::{
     sound_graph::{
         self,
-        graph::{ActiveNodeState, SoundNodeGraph, evaluate_node},
+        audition::{Audition, SharedAudition, render_node_source},
+        automation::{
+            AUTOMATION_COUNT, AutomationSmoothing, MacroSnapshot, macro_string_to_value,
+            macro_value_to_string,
+        },
+        compiler::{DEBOUNCE, detached_state},
+        graph::{ActiveNodeState, SoundNodeGraph},
     },
-    sound_map::GenericSource,
-};
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+    sound_map::{AtomicF32, GenericSource},
 };
+use std::sync::{Arc, Mutex};
 
 pub struct NodeSound {
     params: Arc<NodeSoundParams>,
-    sample_rate: Arc<Mutex<f32>>,
-    bpm: Arc<Mutex<f32>>,
+    sample_rate: Arc<AtomicF32>,
+    bpm: Arc<AtomicF32>,
+    /// The graph's shared handles from the last block the graph could be locked in, so the audio
+    /// thread never has to wait for the editor.
+    automations: DAWAutomations,
+    transport: DAWTransport,
+    automation_macros: [MacroSnapshot; AUTOMATION_COUNT],
+    automation_smoothers: [Smoother<f32>; AUTOMATION_COUNT],
+    /// The value and smoothing each automation smoother was last given, as setting a target
+    /// restarts its ramp.
+    automation_targets: [(f32, AutomationSmoothing); AUTOMATION_COUNT],
+    input: DAWInput,
+    scope_tap: ScopeTap,
     sound_result: Arc<Mutex<Option<GenericSource>>>,
+    audition: SharedAudition,
     total_idx: usize,
 }
 
@@ -90,16 +106,25 @@ impl Default for NodeSound {
         let params = NodeSoundParams::default();
         Self {
             params: Arc::new(params),
-            sample_rate: Arc::new(Mutex::new(48000.0)),
-            bpm: Arc::new(Mutex::new(120.0)),
+            sample_rate: Arc::new(AtomicF32::new(48000.0)),
+            bpm: Arc::new(AtomicF32::new(120.0)),
+            automations: DAWAutomations::default(),
+            transport: DAWTransport::default(),
+            automation_macros: [MacroSnapshot::default(); AUTOMATION_COUNT],
+            automation_smoothers: [0; AUTOMATION_COUNT]
+                .map(|_| Smoother::new(SmoothingStyle::None)),
+            automation_targets: [(0.0, AutomationSmoothing::None); AUTOMATION_COUNT],
+            input: DAWInput::default(),
+            scope_tap: ScopeTap::default(),
             sound_result: Arc::new(Mutex::new(None)),
+            audition: Arc::new(Mutex::new(None)),
             total_idx: 0,
         }
     }
 }
 
 macro_rules! mkparam {
-    ($field: ident, $name: literal) => {
+    ($field: ident, $name: literal, $idx: literal, $graph: ident) => {
         let $field = FloatParam::new(
             $name,
             0.0,
@@ -109,38 +134,57 @@ macro_rules! mkparam {
             },
         )
         .with_smoother(SmoothingStyle::None)
-        .with_step_size(0.01);
+        .with_step_size(0.01)
+        .with_value_to_string(macro_value_to_string($graph.clone(), $idx))
+        .with_string_to_value(macro_string_to_value($graph.clone(), $idx));
     };
 }
 
+impl NodeSoundParams {
+    fn automation_params(&self) -> [&FloatParam; AUTOMATION_COUNT] {
+        [
+            &self.a1, &self.a2, &self.a3, &self.a4, &self.a5, &self.a6, &self.a7, &self.a8,
+            &self.a9, &self.a10, &self.a11, &self.a12, &self.a13, &self.a14, &self.a15, &self.a16,
+            &self.a17, &self.a18,
+        ]
+    }
+}
+
+fn smoothing_style(smoothing: AutomationSmoothing) -> SmoothingStyle {
+    match smoothing {
+        AutomationSmoothing::None => SmoothingStyle::None,
+        AutomationSmoothing::Linear(ms) => SmoothingStyle::Linear(ms),
+        AutomationSmoothing::Exponential(ms) => SmoothingStyle::Exponential(ms),
+    }
+}
+
 impl Default for NodeSoundParams {
     fn default() -> Self {
-        mkparam! {a1, "A1"}
-        mkparam! {a2, "A2"}
-        mkparam! {a3, "A3"}
-        mkparam! {a4, "A4"}
-        mkparam! {a5, "A5"}
-        mkparam! {a6, "A6"}
-        mkparam! {a7, "A7"}
-        mkparam! {a8, "A8"}
-        mkparam! {a9, "A9"}
-        mkparam! {a10, "A10"}
-        mkparam! {a11, "A11"}
-        mkparam! {a12, "A12"}
-        mkparam! {a13, "A13"}
-        mkparam! {a14, "A14"}
-        mkparam! {a15, "A15"}
-        mkparam! {a16, "A16"}
-        mkparam! {a17, "A17"}
-        mkparam! {a18, "A18"}
+        let graph = Arc::new(Mutex::new(
+            sound_graph::graph::SoundNodeGraph::new_vst_effect(),
+        ));
+        mkparam! {a1, "A1", 0, graph}
+        mkparam! {a2, "A2", 1, graph}
+        mkparam! {a3, "A3", 2, graph}
+        mkparam! {a4, "A4", 3, graph}
+        mkparam! {a5, "A5", 4, graph}
+        mkparam! {a6, "A6", 5, graph}
+        mkparam! {a7, "A7", 6, graph}
+        mkparam! {a8, "A8", 7, graph}
+        mkparam! {a9, "A9", 8, graph}
+        mkparam! {a10, "A10", 9, graph}
+        mkparam! {a11, "A11", 10, graph}
+        mkparam! {a12, "A12", 11, graph}
+        mkparam! {a13, "A13", 12, graph}
+        mkparam! {a14, "A14", 13, graph}
+        mkparam! {a15, "A15", 14, graph}
+        mkparam! {a16, "A16", 15, graph}
+        mkparam! {a17, "A17", 16, graph}
+        mkparam! {a18, "A18", 17, graph}
 
         Self {
             editor_state: EguiState::from_size(1280, 720),
-            plugin_state: PluginPresetState {
-                graph: Arc::new(Mutex::new(
-                    sound_graph::graph::SoundNodeGraph::new_vst_effect(),
-                )),
-            },
+            plugin_state: PluginPresetState { graph },
             a1,
             a2,
             a3,
@@ -163,21 +207,13 @@ impl Default for NodeSoundParams {
     }
 }
 
-macro_rules! mkparamgetter {
-    ($field: ident, $idx: literal, $self: ident, $buff: ident) => {
-        let $field = $self.params.$field.value();
-        match $buff[$idx].lock() {
-            Ok(mut x) => {
-                *x = $field;
-            }
-            Err(_x) => {}
-        }
-    };
-}
-
 pub enum BackgroundTasks {
     MidiFileOpen(Arc<Mutex<FileManager>>),
     WavFileOpen(Arc<Mutex<FileManager>>),
+    PatchFileDialog(Arc<Mutex<FileManager>>),
+    RecordFileDialog(Arc<Mutex<FileManager>>),
+    MidiExportDialog(Arc<Mutex<FileManager>>),
+    TuningFileDialog(Arc<Mutex<FileManager>>),
 }
 
 impl Plugin for NodeSound {
@@ -188,11 +224,23 @@ impl Plugin for NodeSound {
 
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
-        main_input_channels: NonZeroU32::new(2),
-        main_output_channels: NonZeroU32::new(2),
-        ..AudioIOLayout::const_default()
-    }];
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+            aux_input_ports: &[new_nonzero_u32(2)],
+            names: PortNames {
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+            ..AudioIOLayout::const_default()
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+            ..AudioIOLayout::const_default()
+        },
+    ];
 
     type SysExMessage = ();
     type BackgroundTask = BackgroundTasks;
@@ -249,6 +297,30 @@ impl Plugin for NodeSound {
                     }
                 };
             }
+            BackgroundTasks::PatchFileDialog(files) => {
+                match files.lock() {
+                    Err(_x) => {}
+                    Ok(mut x) => x.open_patch_dialogs(),
+                };
+            }
+            BackgroundTasks::RecordFileDialog(files) => {
+                match files.lock() {
+                    Err(_x) => {}
+                    Ok(mut x) => x.open_record_dialog(),
+                };
+            }
+            BackgroundTasks::MidiExportDialog(files) => {
+                match files.lock() {
+                    Err(_x) => {}
+                    Ok(mut x) => x.open_midi_export_dialog(),
+                };
+            }
+            BackgroundTasks::TuningFileDialog(files) => {
+                match files.lock() {
+                    Err(_x) => {}
+                    Ok(mut x) => x.open_tuning_dialog(),
+                };
+            }
         })
     }
 
@@ -263,15 +335,11 @@ impl Plugin for NodeSound {
                 self.params.clone(),
                 false,
                 None,
+                self.audition.clone(),
             ),
             |_, _| {},
             move |egui_ctx, setter, state| {
-                let sample_rate = &mut match state.2.lock() {
-                    Ok(x) => x,
-                    Err(_x) => {
-                        return;
-                    }
-                };
+                let sample_rate = state.2.load();
                 let graph = &mut match state.0.lock() {
                     Ok(x) => x,
                     Err(_x) => {
@@ -285,30 +353,20 @@ impl Plugin for NodeSound {
                         egui::ScrollArea::horizontal().show(ui, |ui| {
                             ui.horizontal(|ui| {
                                 let params = &state.4;
-                                let knobs = [
-                                    ("A1", &params.a1),
-                                    ("A2", &params.a2),
-                                    ("A3", &params.a3),
-                                    ("A4", &params.a4),
-                                    ("A5", &params.a5),
-                                    ("A6", &params.a6),
-                                    ("A7", &params.a7),
-                                    ("A8", &params.a8),
-                                    ("A9", &params.a9),
-                                    ("A10", &params.a10),
-                                    ("A11", &params.a11),
-                                    ("A12", &params.a12),
-                                    ("A13", &params.a13),
-                                    ("A14", &params.a14),
-                                    ("A15", &params.a15),
-                                    ("A16", &params.a16),
-                                    ("A17", &params.a17),
-                                    ("A18", &params.a18),
-                                ];
-
-                                for (label, param) in knobs.iter() {
+                                let macros = &mut graph.state.user_state.automation_macros;
+                                if ui.button("⚙").on_hover_text("Edit macros").clicked() {
+                                    macros.open = !macros.open;
+                                }
+                                let mut changed = false;
+                                for (idx, param) in
+                                    params.automation_params().into_iter().enumerate()
+                                {
+                                    let automation_macro = match macros.get_mut(idx) {
+                                        Some(x) => x,
+                                        None => break,
+                                    };
                                     ui.vertical(|ui| {
-                                        ui.label(*label);
+                                        ui.label(automation_macro.name.clone());
                                         let param_value = param.value();
                                         let mut current_value = param_value;
                                         let response = ui.add(
@@ -317,12 +375,22 @@ impl Plugin for NodeSound {
                                                 .drag_length(50.0)
                                                 .diameter(15.0),
                                         );
+                                        let response = response
+                                            .on_hover_text(automation_macro.format(current_value));
                                         if response.changed() && current_value != param_value {
-                                            setter.set_parameter(*param, current_value);
+                                            setter.set_parameter(param, current_value);
                                         }
+                                        let smoothing = &mut automation_macro.smoothing;
+                                        let name = smoothing.name();
+                                        let menu = ui.menu_button("~", |ui| smoothing.ui(ui, idx));
+                                        menu.response.on_hover_text(format!("Smoothing: {}", name));
+                                        changed |= menu.inner == Some(true);
                                     });
                                     ui.add_space(2.0);
                                 }
+                                if changed {
+                                    graph.state.user_state.is_saved = false;
+                                }
                                 ui.separator();
                                 match error {
                                     Some(err) => {
@@ -335,65 +403,65 @@ impl Plugin for NodeSound {
                     });
                 });
                 graph.update_root(egui_ctx);
-                if !state.5 || graph.state.user_state.active_node.is_playing() {
-                    graph.state.user_state.active_node = ActiveNodeState::NoNode;
-                    match graph.state.user_state.vst_output_node_id {
-                        Some(x) => {
+                let mut recompile = false;
+                if graph.state.user_state.active_modified {
+                    graph.state.user_state.active_modified = false;
+                    recompile = true;
+                    let audition = match graph.state.user_state.active_node {
+                        ActiveNodeState::PlayingNode(node_id) => {
                             graph
                                 .state
                                 ._unserializeable_state
                                 .queue
-                                .set_sample_rate(**sample_rate);
-                            graph.state.user_state.wavetables.clear();
-                            graph.state._unserializeable_state.queue.set_note_speed(1.0);
+                                .set_sample_rate(sample_rate);
                             graph
                                 .state
                                 ._unserializeable_state
                                 .queue
                                 .set_bpm(state.3.clone());
-                            match evaluate_node(
-                                &graph.state.editor_state.graph.clone(),
-                                x,
-                                &mut HashMap::new(),
-                                &graph.state._unserializeable_state.node_definitions.clone(),
-                                &mut graph.state,
-                            ) {
-                                Ok(val) => {
-                                    let source_id = match val.try_to_source() {
-                                        Err(_x) => return,
-                                        Ok(x) => x,
-                                    }
-                                    .clone();
-                                    let sound = match graph
-                                        .state
-                                        ._unserializeable_state
-                                        .queue
-                                        .clone_sound(source_id.clone())
-                                    {
-                                        Err(_err) => {
-                                            return;
-                                        }
-                                        Ok(x) => x,
-                                    };
-                                    let sound_result = &mut match state.1.lock() {
-                                        Ok(x) => x,
-                                        Err(_x) => {
-                                            return;
-                                        }
-                                    };
-                                    **sound_result = Some(sound);
-                                    state.5 = true;
-                                }
+                            match render_node_source(&mut graph.state, node_id) {
+                                Ok(x) => Some(Audition::new(x)),
                                 Err(err) => {
                                     *error = Some(format!("{:?}", err));
-                                    graph.state._unserializeable_state.queue.clear();
+                                    None
                                 }
-                            };
+                            }
                         }
-                        None => {
-                            graph.state._unserializeable_state.queue.clear();
+                        _ => None,
+                    };
+                    match state.7.lock() {
+                        Ok(mut x) => *x = audition,
+                        Err(_x) => {}
+                    }
+                }
+                let revision = graph.state.user_state.history.revision();
+                let compiler = &mut graph.state._unserializeable_state.compiler;
+                if recompile {
+                    compiler.invalidate();
+                }
+                if compiler.should_compile(revision) {
+                    let job = detached_state(&graph.state, sample_rate, state.3.clone());
+                    graph.state._unserializeable_state.compiler.start(
+                        job,
+                        revision,
+                        vec![(0, 1.0)],
+                    );
+                }
+                let compiler = &mut graph.state._unserializeable_state.compiler;
+                match compiler.try_finish() {
+                    Some(Ok(mut sources)) => {
+                        *error = None;
+                        match state.1.lock() {
+                            Ok(mut sound_result) => *sound_result = sources.pop().map(|(_, x)| x),
+                            Err(_x) => {}
                         }
+                        state.5 = true;
                     }
+                    Some(Err(err)) => *error = Some(err),
+                    None => {}
+                }
+                if !compiler.is_idle(revision) {
+                    egui_ctx.request_repaint_after(DEBOUNCE / 4);
                 }
             },
         )
@@ -405,92 +473,163 @@ impl Plugin for NodeSound {
         _buffer_config: &BufferConfig,
         _context: &mut impl InitContext<Self>,
     ) -> bool {
+        // Loading a state replaces the graph and reinitializes the plugin, so this covers the
+        // history of every graph the audio thread reads from.
+        if let Ok(graph) = self.params.plugin_state.graph.lock() {
+            graph.state._unserializeable_state.input.0.allocate();
+        }
+        for (param, smoother) in self
+            .params
+            .automation_params()
+            .iter()
+            .zip(self.automation_smoothers.iter())
+        {
+            smoother.reset(param.value());
+        }
+        for (param, target) in self
+            .params
+            .automation_params()
+            .iter()
+            .zip(self.automation_targets.iter_mut())
+        {
+            target.0 = param.value();
+        }
         true
     }
 
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
+        aux: &mut AuxiliaryBuffers,
         context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        match self.sample_rate.try_lock() {
-            Ok(mut x) => *x = context.transport().sample_rate,
-            Err(_x) => {}
-        };
-        let state = &match self.params.plugin_state.graph.lock() {
-            Ok(x) => x,
-            Err(_x) => {
-                return ProcessStatus::KeepAlive;
+        let sample_rate = context.transport().sample_rate;
+        self.sample_rate.store(sample_rate);
+        self.bpm
+            .store(context.transport().tempo.unwrap_or(120.0) as f32);
+        // The editor holds the graph while it draws, in which case the handles from the last
+        // block are used.
+        if let Ok(graph) = self.params.plugin_state.graph.try_lock() {
+            let state = &graph.state;
+            self.automations = state._unserializeable_state.automations.clone();
+            self.transport = state._unserializeable_state.transport.clone();
+            self.automation_macros = state.user_state.automation_macros.snapshot();
+            self.input = state._unserializeable_state.input.clone();
+            self.scope_tap = state.user_state.scopes.tap.clone();
+            match state.user_state.files.try_lock() {
+                Ok(x) => {
+                    if x.midi_active.is_some() {
+                        context.execute_background(BackgroundTasks::MidiFileOpen(
+                            state.user_state.files.clone(),
+                        ));
+                    }
+                    if x.wav_active.is_some() {
+                        context.execute_background(BackgroundTasks::WavFileOpen(
+                            state.user_state.files.clone(),
+                        ));
+                    }
+                    if x.has_patch_dialog() {
+                        context.execute_background(BackgroundTasks::PatchFileDialog(
+                            state.user_state.files.clone(),
+                        ));
+                    }
+                    if x.record_active.is_some() {
+                        context.execute_background(BackgroundTasks::RecordFileDialog(
+                            state.user_state.files.clone(),
+                        ));
+                    }
+                    if x.midi_export_active.is_some() {
+                        context.execute_background(BackgroundTasks::MidiExportDialog(
+                            state.user_state.files.clone(),
+                        ));
+                    }
+                    if x.tuning_active.is_some() {
+                        context.execute_background(BackgroundTasks::TuningFileDialog(
+                            state.user_state.files.clone(),
+                        ));
+                    }
+                }
+                Err(_x) => {}
             }
         }
-        .state;
-        match self.bpm.try_lock() {
-            Ok(mut x) => {
-                *x = context.transport().tempo.unwrap_or(120.0) as f32;
+        let transport = context.transport();
+        let song_beats = transport.pos_beats().unwrap_or_default();
+        let beats_per_sample = transport.tempo.unwrap_or(120.0) / 60.0 / sample_rate as f64;
+        self.transport.0.set_playing(transport.playing);
+        self.transport.0.set_signature(
+            transport.time_sig_numerator.unwrap_or(4) as f32,
+            transport.time_sig_denominator.unwrap_or(4) as f32,
+        );
+        // The smoothers only get a new target when the value changes, otherwise their ramps would
+        // restart every block. They are stepped every sample.
+        for (idx, param) in self.params.automation_params().iter().enumerate() {
+            let smoother = &mut self.automation_smoothers[idx];
+            let (target, smoothing) = &mut self.automation_targets[idx];
+            let macro_smoothing = self.automation_macros[idx].smoothing;
+            if *smoothing != macro_smoothing {
+                *smoothing = macro_smoothing;
+                smoother.style = smoothing_style(macro_smoothing);
             }
-            Err(_x) => {}
-        };
-        match state.user_state.files.try_lock() {
-            Ok(x) => {
-                if x.midi_active.is_some() {
-                    context.execute_background(BackgroundTasks::MidiFileOpen(
-                        state.user_state.files.clone(),
-                    ));
-                }
-                if x.wav_active.is_some() {
-                    context.execute_background(BackgroundTasks::WavFileOpen(
-                        state.user_state.files.clone(),
-                    ));
-                }
+            if *target != param.value() {
+                *target = param.value();
+                smoother.set_target(sample_rate, param.value());
             }
-            Err(_x) => {}
         }
-        let automations = state._unserializeable_state.automations.0.clone();
-        let input = state._unserializeable_state.input.0.clone();
+        let input = self.input.0.clone();
+        let scope_tap = self.scope_tap.clone();
         let size = buffer.samples();
         let output = buffer.as_slice();
+        let sidechain = aux.inputs.first().map(|x| x.as_slice_immutable());
         let mut sound_result = match self.sound_result.try_lock() {
             Ok(x) => x,
             Err(_x) => {
                 return ProcessStatus::KeepAlive;
             }
         };
+        let mut audition = self.audition.try_lock().ok();
         for sample_idx in 0..size {
-            match input.try_lock() {
-                Ok(mut x) => {
-                    x.0 = output[0][sample_idx];
-                    x.1 = output[1][sample_idx];
-                }
-                _ => {}
+            self.transport
+                .0
+                .set_beats((song_beats + sample_idx as f64 * beats_per_sample) as f32);
+            for ((smoother, automation_macro), automation) in self
+                .automation_smoothers
+                .iter()
+                .zip(self.automation_macros.iter())
+                .zip(self.automations.0.iter())
+            {
+                automation.store(automation_macro.map(smoother.next()));
             }
+            // A mono sidechain feeds both channels, no sidechain is silence.
+            let sidechain_sample = |channel: usize| {
+                sidechain
+                    .and_then(|x| x.get(channel).or(x.first()))
+                    .map(|x| x[sample_idx])
+                    .unwrap_or_default()
+            };
+            input.push([
+                [output[0][sample_idx], output[1][sample_idx]],
+                [sidechain_sample(0), sidechain_sample(1)],
+            ]);
+            let time_index = (sample_idx + self.total_idx) as f32;
+            input.set_now(time_index);
             output[0][sample_idx] = 0.0;
             output[1][sample_idx] = 0.0;
-            mkparamgetter!(a1, 0, self, automations);
-            mkparamgetter!(a2, 1, self, automations);
-            mkparamgetter!(a3, 2, self, automations);
-            mkparamgetter!(a4, 3, self, automations);
-            mkparamgetter!(a5, 4, self, automations);
-            mkparamgetter!(a6, 5, self, automations);
-            mkparamgetter!(a7, 6, self, automations);
-            mkparamgetter!(a8, 7, self, automations);
-            mkparamgetter!(a9, 8, self, automations);
-            mkparamgetter!(a10, 9, self, automations);
-            mkparamgetter!(a11, 10, self, automations);
-            mkparamgetter!(a12, 11, self, automations);
-            mkparamgetter!(a13, 12, self, automations);
-            mkparamgetter!(a14, 13, self, automations);
-            mkparamgetter!(a15, 14, self, automations);
-            mkparamgetter!(a16, 15, self, automations);
-            mkparamgetter!(a17, 16, self, automations);
-            mkparamgetter!(a18, 17, self, automations);
+            if let Some(Some(audition)) = audition.as_deref_mut() {
+                let (left_sample, right_sample) = audition.next();
+                output[0][sample_idx] = left_sample;
+                output[1][sample_idx] = right_sample;
+                scope_tap.0.push((left_sample + right_sample) * 0.5);
+                continue;
+            }
             match &mut *sound_result {
                 Some(source) => {
-                    let time_index = (sample_idx + self.total_idx) as f32;
                     let left_sample = source.next(time_index, 0).unwrap_or_default();
                     let right_sample = source.next(time_index, 1).unwrap_or_default();
                     output[0][sample_idx] = left_sample.clamp(-1.0, 1.0);
                     output[1][sample_idx] = right_sample.clamp(-1.0, 1.0);
+                    scope_tap
+                        .0
+                        .push((output[0][sample_idx] + output[1][sample_idx]) * 0.5);
                 }
                 None => {
                     output[0][sample_idx] = 0.0;